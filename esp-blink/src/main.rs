@@ -1,12 +1,15 @@
 // Import necessary modules and types from the standard library and ESP-IDF HAL
 use std::thread::sleep; // For adding delays
 use std::time::Duration; // For specifying the duration of delays
-use std::collections::HashMap; // For Morse code mapping
 
 use esp_idf_svc::sys::link_patches; // For linking runtime patches
 use esp_idf_hal::peripherals::Peripherals; // For accessing GPIO peripherals
 use esp_idf_hal::gpio::*; // For GPIO operations
 use esp_idf_sys::EspError; // For error handling
+use morse::{Element, Timing}; // For Morse code mapping and timing
+
+// Dot duration, dashes and gaps follow from it
+const TIMING: Timing = Timing::from_unit_ms(100);
 
 // Define a struct to encapsulate the buzzer pin
 struct Buzzer {
@@ -25,56 +28,23 @@ impl Buzzer {
         self.pin.set_low() // Turn off buzzer
     }
 
-    // Method to send a dot (.)
-    fn dot(&mut self) -> Result<(), EspError> {
-        self.on()?;
-        sleep(Duration::from_millis(100)); // Dot duration
-        self.off()?;
-        sleep(Duration::from_millis(100)); // Space between dots/dashes
-        Ok(())
-    }
-
-    // Method to send a dash (-)
-    fn dash(&mut self) -> Result<(), EspError> {
-        self.on()?;
-        sleep(Duration::from_millis(300)); // Dash duration
-        self.off()?;
-        sleep(Duration::from_millis(100)); // Space between dots/dashes
-        Ok(())
-    }
-
-    // Method to send a letter
-    fn letter(&mut self, letter: char) -> Result<(), EspError> {
-        let morse_code_map: HashMap<char, &str> = [
-            ('A', ".-"), ('B', "-..."), ('C', "-.-."), ('D', "-.."), ('E', "."), ('F', "..-."), 
-            ('G', "--."), ('H', "...."), ('I', ".."), ('J', ".---"), ('K', "-.-"), ('L', ".-.."), 
-            ('M', "--"), ('N', "-."), ('O', "---"), ('P', ".--."), ('Q', "--.-"), ('R', ".-."), 
-            ('S', "..."), ('T', "-"), ('U', "..-"), ('V', "...-"), ('W', ".--"), ('X', "-..-"), 
-            ('Y', "-.--"), ('Z', "--.."), ('0', "-----"), ('1', ".----"), ('2', "..---"), 
-            ('3', "...--"), ('4', "....-"), ('5', "....."), ('6', "-...."), ('7', "--..."), 
-            ('8', "---.."), ('9', "----."), (' ', "/"), // Space between words
-        ].iter().cloned().collect();
-
-        if let Some(morse_code) = morse_code_map.get(&letter) {
-            for symbol in morse_code.chars() {
-                match symbol {
-                    '.' => self.dot()?,
-                    '-' => self.dash()?,
-                    '/' => sleep(Duration::from_millis(700)), // Space between words
-                    _ => {}
-                }
-            }
-            sleep(Duration::from_millis(300)); // Space between letters
+    // Method to key an element, on or off for its duration
+    fn key(&mut self, element: Element) -> Result<(), EspError> {
+        if element.on {
+            self.on()?;
+        } else {
+            self.off()?;
         }
+        sleep(Duration::from_millis(TIMING.millis(element).into()));
         Ok(())
     }
 
     // Method to send a message
     fn message(&mut self, message: &str) -> Result<(), EspError> {
-        for letter in message.chars() {
-            self.letter(letter)?;
+        for element in morse::encode(message) {
+            self.key(element)?;
         }
-        Ok(())
+        self.key(Element::off(morse::WORD_GAP)) // Space before the next message
     }
 }
 
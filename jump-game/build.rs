@@ -1,10 +1,312 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::{env, fs};
+
 fn main() {
+    generate_sprites();
+
+    // Unit tests are built for the host, which links without the ESP32 linker scripts
+    if std::env::var("CARGO_CFG_TARGET_ARCH").is_ok_and(|arch| arch != "xtensa") {
+        return;
+    }
     linker_be_nice();
     println!("cargo:rustc-link-arg=-Tdefmt.x");
     // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
     println!("cargo:rustc-link-arg=-Tlinkall.x");
 }
 
+/// Converts the PBM images of `assets/` into `$OUT_DIR/sprites.rs`.
+///
+/// For `assets/trex-duck.pbm` it writes the 1 bpp bitmap `SPRITE_TREX_DUCK`, its size
+/// `TREX_DUCK_WIDTH` and `TREX_DUCK_HEIGHT`, and the image `RAW_TREX_DUCK`. Set pixels
+/// (black in an image editor) are the lit pixels of the display.
+///
+/// A sprite sheet has its frames side by side and a `# frames N` comment in its
+/// header. It is split into `N` frames of the same size, `SPRITE_NAME` and `RAW_NAME`
+/// become arrays of `NAME_FRAMES` frames and `NAME_WIDTH` is the width of a frame.
+///
+/// Every sprite also gets a copy of half its height for the displays lower than
+/// 64 pixels, `SPRITE_NAME_HALF`, `RAW_NAME_HALF` and `NAME_HALF_HEIGHT`.
+fn generate_sprites() {
+    let assets = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", assets.display());
+
+    let mut paths: Vec<_> = fs::read_dir(&assets)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pbm"))
+        .collect();
+    paths.sort();
+
+    let mut out =
+        String::from("// Generated by build.rs from the images in assets/, do not edit\n");
+    for path in paths {
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let name = path
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_uppercase()
+            .replace('-', "_");
+        let bytes = fs::read(&path).unwrap();
+        let frames = Bitmap::parse_pbm(&bytes)
+            .and_then(|image| image.split_frames(sheet_frames(&bytes)?))
+            .unwrap_or_else(|err| panic!("assets/{file_name}: {err}"));
+        let (width, height) = (frames[0].width, frames[0].height);
+
+        let count = frames.len();
+        writeln!(out).unwrap();
+        match count {
+            1 => writeln!(out, "// {file_name}, {width} x {height} px").unwrap(),
+            _ => writeln!(
+                out,
+                "// {file_name}, {count} frames of {width} x {height} px"
+            )
+            .unwrap(),
+        }
+        writeln!(out, "pub const {name}_WIDTH: u32 = {width};").unwrap();
+        if count > 1 {
+            writeln!(out, "pub const {name}_FRAMES: usize = {count};").unwrap();
+        }
+        write_frames(&mut out, &name, "", &frames);
+        let half: Vec<Bitmap> = frames.iter().map(Bitmap::half_height).collect();
+        write_frames(&mut out, &name, "_HALF", &half);
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("sprites.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Writes the height, the bitmap and the image of the sprite `name`, with `suffix`
+/// after their names.
+fn write_frames(out: &mut String, name: &str, suffix: &str, frames: &[Bitmap]) {
+    let height = frames[0].height;
+    writeln!(out, "pub const {name}{suffix}_HEIGHT: u32 = {height};").unwrap();
+    let raw = |sprite: &str| format!("ImageRaw::new(&{sprite}, {name}_WIDTH)");
+    if let [image] = frames {
+        let data = image.data_literal();
+        writeln!(
+            out,
+            "pub const SPRITE_{name}{suffix}: [u8; {}] = {data};",
+            image.data.len()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "pub const RAW_{name}{suffix}: ImageRaw<'static, BinaryColor> = {};",
+            raw(&format!("SPRITE_{name}{suffix}"))
+        )
+        .unwrap();
+    } else {
+        let count = frames.len();
+        let data: Vec<String> = frames.iter().map(Bitmap::data_literal).collect();
+        writeln!(
+            out,
+            "pub const SPRITE_{name}{suffix}: [[u8; {}]; {count}] = [{}];",
+            frames[0].data.len(),
+            data.join(", ")
+        )
+        .unwrap();
+        let raws: Vec<String> = (0..count)
+            .map(|frame| raw(&format!("SPRITE_{name}{suffix}[{frame}]")))
+            .collect();
+        writeln!(
+            out,
+            "pub const RAW_{name}{suffix}: [ImageRaw<'static, BinaryColor>; {count}] = [{}];",
+            raws.join(", ")
+        )
+        .unwrap();
+    }
+}
+
+/// 1 bpp image in the `ImageRaw` layout: rows padded to a whole byte, the most
+/// significant bit being the leftmost pixel.
+struct Bitmap {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Reads a plain (`P1`) or binary (`P4`) PBM file.
+    fn parse_pbm(bytes: &[u8]) -> Result<Self, String> {
+        let mut pos = 0;
+        let magic = pbm_token(bytes, &mut pos).ok_or("missing PBM header")?;
+        let width = pbm_number(bytes, &mut pos)?;
+        let height = pbm_number(bytes, &mut pos)?;
+        let bytes_per_row = width.div_ceil(8) as usize;
+        let size = bytes_per_row * height as usize;
+
+        let data = match magic {
+            b"P1" => {
+                let mut data = vec![0; size];
+                let pixel_count = (width * height) as usize;
+                let mut idx = 0;
+                let mut comment = false;
+                for &byte in &bytes[pos..] {
+                    match byte {
+                        b'#' => comment = true,
+                        b'\n' => comment = false,
+                        _ if comment || byte.is_ascii_whitespace() => (),
+                        b'0' | b'1' if idx < pixel_count => {
+                            let (x, y) = (idx % width as usize, idx / width as usize);
+                            if byte == b'1' {
+                                data[y * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+                            }
+                            idx += 1;
+                        }
+                        b'0' | b'1' => return Err("too many pixels".into()),
+                        _ => return Err(format!("unexpected pixel value {:?}", byte as char)),
+                    }
+                }
+                if idx < pixel_count {
+                    return Err("not enough pixels".into());
+                }
+                data
+            }
+            b"P4" => {
+                // A single whitespace separates the header from the pixels
+                let raster = bytes
+                    .get(pos + 1..pos + 1 + size)
+                    .ok_or("not enough pixels")?;
+                let mut data = raster.to_vec();
+                // The padding bits are not part of the image, clear them
+                if width % 8 != 0 {
+                    let mask = 0xFFu8 << (8 - width % 8);
+                    for row in data.chunks_mut(bytes_per_row) {
+                        row[bytes_per_row - 1] &= mask;
+                    }
+                }
+                data
+            }
+            _ => return Err("only the P1 and P4 PBM formats are supported".into()),
+        };
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Cuts a sprite sheet into `count` frames of the same width, from left to right.
+    fn split_frames(self, count: u32) -> Result<Vec<Self>, String> {
+        if count == 1 {
+            return Ok(vec![self]);
+        }
+        if self.width % count != 0 {
+            return Err(format!("{} px wide, not {count} frames", self.width));
+        }
+        let width = self.width / count;
+        let frames = (0..count)
+            .map(|frame| {
+                let mut data = vec![0; width.div_ceil(8) as usize * self.height as usize];
+                for y in 0..self.height {
+                    for x in 0..width {
+                        if self.pixel(frame * width + x, y) {
+                            let idx = (y * width.div_ceil(8) + x / 8) as usize;
+                            data[idx] |= 0x80 >> (x % 8);
+                        }
+                    }
+                }
+                Self {
+                    width,
+                    height: self.height,
+                    data,
+                }
+            })
+            .collect();
+        Ok(frames)
+    }
+
+    /// Merges the rows two by two, a pixel is set when either of them is.
+    fn half_height(&self) -> Self {
+        let bytes_per_row = self.width.div_ceil(8) as usize;
+        let data = self
+            .data
+            .chunks(2 * bytes_per_row)
+            .flat_map(|rows| {
+                let (top, bottom) = rows.split_at(bytes_per_row);
+                (0..bytes_per_row).map(move |idx| top[idx] | bottom.get(idx).unwrap_or(&0))
+            })
+            .collect();
+        Self {
+            width: self.width,
+            height: self.height.div_ceil(2),
+            data,
+        }
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> bool {
+        let byte = self.data[(y * self.width.div_ceil(8) + x / 8) as usize];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+
+    fn data_literal(&self) -> String {
+        let bytes: Vec<String> = self
+            .data
+            .iter()
+            .map(|byte| format!("0x{byte:02x}"))
+            .collect();
+        format!("[{}]", bytes.join(", "))
+    }
+}
+
+/// Number of frames of a sprite sheet, from its `# frames N` comment, 1 without one.
+fn sheet_frames(bytes: &[u8]) -> Result<u32, String> {
+    let comment = bytes
+        .split(|&byte| byte == b'\n')
+        .filter_map(|line| line.strip_prefix(b"#"))
+        .map(|line| String::from_utf8_lossy(line).trim().to_string())
+        .find_map(|line| {
+            line.strip_prefix("frames")
+                .map(|count| count.trim().to_string())
+        });
+    match comment {
+        None => Ok(1),
+        Some(count) => match count.parse() {
+            Ok(0) | Err(_) => Err(format!("invalid frame count {count:?}")),
+            Ok(count) => Ok(count),
+        },
+    }
+}
+
+/// Next whitespace separated token of a PBM header, skipping the comments.
+fn pbm_token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while bytes.get(*pos)?.is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if bytes[*pos] != b'#' {
+            break;
+        }
+        while bytes.get(*pos)? != &b'\n' {
+            *pos += 1;
+        }
+    }
+    let start = *pos;
+    while bytes
+        .get(*pos)
+        .is_some_and(|byte| !byte.is_ascii_whitespace())
+    {
+        *pos += 1;
+    }
+    Some(&bytes[start..*pos])
+}
+
+fn pbm_number(bytes: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let token = pbm_token(bytes, pos).ok_or("truncated PBM header")?;
+    let number = std::str::from_utf8(token)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| format!("invalid size {:?}", String::from_utf8_lossy(token)))?;
+    match number {
+        0 => Err("empty image".into()),
+        _ => Ok(number),
+    }
+}
+
 fn linker_be_nice() {
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
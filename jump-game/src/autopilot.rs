@@ -0,0 +1,158 @@
+//! Computer player for the attract mode.
+//!
+//! The autopilot looks at the obstacles the way a player looks at the screen and
+//! returns the `InputEvent` it would press, so it plugs in where the buttons do.
+
+use crate::game::{Game, GameState};
+use crate::highscore::ScoreStorage;
+use crate::input::InputEvent;
+use crate::rng::Rng;
+use crate::sprites::{self, PTERO_HIGH_Y, TREX_X};
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::{Dimensions, DrawTarget, OriginDimensions},
+};
+
+// Updates before the obstacle reaches the T-Rex at which it jumps or ducks
+const LEAD_TICKS: i32 = 2;
+// Time the menu and the game over screen are shown before the next demo run
+const RESTART_DELAY_TICKS: u32 = 50;
+
+/// Plays the game by itself.
+///
+/// Call `update` before each `Game::step` and pass the event it returns, if any,
+/// to `Game::handle_input`. A new run is started from the menu and the game over
+/// screen after `RESTART_DELAY_TICKS`.
+#[derive(Debug, Default)]
+pub struct Autopilot {
+    ducking: bool,
+    // Left edge of the next obstacle on the last update, a new one shows up further right
+    next_x: i32,
+    // Set once the next obstacle got its press, more would be buffered into a second jump
+    pressed: bool,
+    // Updates spent out of a run
+    idle_ticks: u32,
+}
+
+impl Autopilot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks the input for the next update of `game`.
+    pub fn update<D, R, S>(&mut self, game: &Game<D, R, S>) -> Option<InputEvent>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        R: Rng,
+        S: ScoreStorage,
+    {
+        match game.state {
+            GameState::Playing => {
+                self.idle_ticks = 0;
+                self.play(game)
+            }
+            GameState::Paused => Some(InputEvent::Press),
+            GameState::MainMenu | GameState::GameOver => {
+                if self.ducking {
+                    self.ducking = false;
+                    return Some(InputEvent::DuckEnd);
+                }
+                self.idle_ticks += 1;
+                (self.idle_ticks >= RESTART_DELAY_TICKS).then_some(InputEvent::Press)
+            }
+        }
+    }
+
+    fn play<D, R, S>(&mut self, game: &Game<D, R, S>) -> Option<InputEvent>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        R: Rng,
+        S: ScoreStorage,
+    {
+        let trex_front = TREX_X + sprites::RAW_TREX.size().width as i32;
+        let next = game
+            .obstacles
+            .get_current()
+            .iter()
+            .map(|obs| obs.img.bounding_box())
+            .find(|bbox| bbox.top_left.x + bbox.size.width as i32 > TREX_X)?;
+        if next.top_left.x > self.next_x {
+            self.pressed = false;
+        }
+        self.next_x = next.top_left.x;
+
+        let speed = game.obstacles.difficulty.speed(game.score());
+        let close = next.top_left.x - trex_front <= speed * LEAD_TICKS;
+        // The high pterodactyl flies over a ducking T-Rex, everything else is jumped
+        let high = next.top_left.y == game.layout().sprite_y(PTERO_HIGH_Y);
+        match (high, self.ducking) {
+            (true, false) if close => {
+                self.ducking = true;
+                Some(InputEvent::DuckStart)
+            }
+            (false, true) => {
+                self.ducking = false;
+                Some(InputEvent::DuckEnd)
+            }
+            (false, false) if close && !self.pressed => {
+                self.pressed = true;
+                Some(InputEvent::Press)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::DifficultyConfig;
+    use crate::sim::{self, SimGame};
+
+    fn run(game: &mut SimGame, autopilot: &mut Autopilot, ticks: u32) {
+        for _ in 0..ticks {
+            if let Some(event) = autopilot.update(game) {
+                game.handle_input(event);
+            }
+            game.step();
+        }
+    }
+
+    #[test]
+    fn demo_starts_from_the_menu() {
+        let mut game = sim::new_game(3, DifficultyConfig::default());
+        let mut autopilot = Autopilot::new();
+        run(&mut game, &mut autopilot, RESTART_DELAY_TICKS - 1);
+        assert_eq!(game.state, GameState::MainMenu);
+        run(&mut game, &mut autopilot, 1);
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn autopilot_keeps_playing() {
+        for seed in 1..=5 {
+            let mut game = sim::new_game(seed, DifficultyConfig::default());
+            let mut autopilot = Autopilot::new();
+            run(&mut game, &mut autopilot, RESTART_DELAY_TICKS + 2000);
+            assert_eq!(game.state, GameState::Playing, "seed {seed}");
+            assert!(game.score() > 100);
+        }
+    }
+
+    #[test]
+    fn demo_restarts_after_game_over() {
+        let mut game = sim::new_game(7, DifficultyConfig::default());
+        game.handle_input(InputEvent::Press);
+        game.handle_input(InputEvent::DuckStart);
+        while game.state == GameState::Playing {
+            game.step();
+        }
+
+        let mut autopilot = Autopilot::new();
+        autopilot.ducking = true;
+        assert_eq!(autopilot.update(&game), Some(InputEvent::DuckEnd));
+        run(&mut game, &mut autopilot, RESTART_DELAY_TICKS);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.score(), 0);
+    }
+}
@@ -8,18 +8,42 @@
 
 use defmt::info;
 use embassy_executor::Spawner;
-use embassy_time::Timer;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::{Instant, Timer};
 use esp_hal::clock::CpuClock;
 use esp_hal::gpio::{Input, InputConfig, Pull};
+use esp_hal::i2c::master::I2c;
+use esp_hal::ledc::channel::ChannelIFace;
+use esp_hal::ledc::timer::TimerIFace;
+use esp_hal::ledc::{channel, timer, HighSpeed, Ledc};
+use esp_hal::peripherals::{GPIO33, LEDC};
 use esp_hal::time::Rate;
 use esp_hal::timer::timg::TimerGroup;
+use esp_hal::Async;
 use esp_println as _;
 
-use jump_game::game::{Game, GameState};
-use ssd1306::mode::DisplayConfig;
-use ssd1306::prelude::DisplayRotation;
-use ssd1306::size::DisplaySize128x64;
-use ssd1306::{I2CDisplayInterface, Ssd1306};
+use embedded_storage::{ReadStorage, Storage};
+use esp_bootloader_esp_idf::partitions;
+use esp_storage::FlashStorage;
+use jump_game::autopilot::Autopilot;
+use jump_game::difficulty::DifficultyConfig;
+use jump_game::game::{Game, GameState, UPDATE_INTERVAL_MS};
+use jump_game::highscore::{self, MemoryStorage, ScoreStorage, Scores};
+use jump_game::input::{ButtonInput, DuckInput, InputEvent};
+use jump_game::replay::{Recorder, Replay};
+use jump_game::rng::SeededRng;
+use jump_game::sound::{self, SoundEvent};
+#[cfg(feature = "fps")]
+use jump_game::timestep::FrameStats;
+use jump_game::timestep::FixedTimestep;
+use oled_dirty::{PageBuffer, PageSpan};
+#[cfg(not(feature = "sh1106"))]
+use ssd1306::{
+    mode::{BasicMode, DisplayConfig},
+    prelude::{DisplayRotation, I2CInterface},
+    I2CDisplayInterface, Ssd1306,
+};
 
 #[panic_handler]
 fn panic(_: &core::panic::PanicInfo) -> ! {
@@ -30,8 +54,23 @@ fn panic(_: &core::panic::PanicInfo) -> ! {
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
 
+#[cfg(all(feature = "sh1106", feature = "ssd1306-128x32"))]
+compile_error!("the `sh1106` and `ssd1306-128x32` features select different displays, enable one of them");
+
+// Height of the display module, the game lays itself out from the size of its buffer
+#[cfg(feature = "ssd1306-128x32")]
+const DISPLAY_HEIGHT: usize = 32;
+#[cfg(not(feature = "ssd1306-128x32"))]
+const DISPLAY_HEIGHT: usize = 64;
+
+// Time without any button press on the menus before the demo starts
+const DEMO_IDLE_MS: u64 = 30_000;
+
+// Sound effects sent by the game loop to `sound_task`
+static SOUND_EVENTS: Channel<CriticalSectionRawMutex, SoundEvent, 4> = Channel::new();
+
 #[esp_hal_embassy::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
     // generator version: 0.5.0
 
     let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
@@ -53,73 +92,322 @@ async fn main(_spawner: Spawner) {
     .with_sda(peripherals.GPIO23)
     .into_async();
 
-    // Setup the OLED Display
-    let interface = I2CDisplayInterface::new(i2c_bus);
-    let mut display = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
-        .into_buffered_graphics_mode();
-    display.init().unwrap();
-    display.flush().unwrap();
+    // Setup the OLED Display, the game draws in its own buffer and only the parts
+    // that changed are sent to the display
+    let mut screen = Screen::new(i2c_bus);
+
+    spawner
+        .spawn(sound_task(peripherals.LEDC, peripherals.GPIO33))
+        .unwrap();
 
     let button = Input::new(peripherals.GPIO4, InputConfig::default().with_pull(Pull::Up));
+    let duck_button = Input::new(peripherals.GPIO5, InputConfig::default().with_pull(Pull::Up));
 
-    let random_gen = RandomGen::new(esp_hal::rng::Rng::new(peripherals.RNG));
-    let mut game = Game::new(random_gen, display);
-    game.draw_trex().unwrap();
-    let mut clicked_count = 0; // To restart the game when it two times button clicked
+    // The hardware generator only seeds the game, so the runs can be replayed
+    let seed = esp_hal::rng::Rng::new(peripherals.RNG).random();
+    let score_storage = match FlashScoreStorage::new(FlashStorage::new()) {
+        Some(flash) => BoardScoreStorage::Flash(flash),
+        None => {
+            info!("No `scores` partition, the high scores are lost on reset");
+            BoardScoreStorage::Memory(MemoryStorage::new())
+        }
+    };
+    let mut game = Game::new(
+        SeededRng::new(seed),
+        PageBuffer::<128, { DISPLAY_HEIGHT / 8 }>::new(),
+        score_storage,
+        DifficultyConfig::default(),
+    );
+    let mut button_input = ButtonInput::new();
+    let mut duck_input = DuckInput::new();
+    let mut recorder = Recorder::new();
+    let mut replay: Option<Replay> = None;
+    let mut demo: Option<Autopilot> = None;
+    let mut last_input_ms = Instant::now().as_millis();
 
     info!("Starting Game!");
 
+    // Physics run at a fixed rate, frames are drawn whenever the display is ready
+    let mut timestep = FixedTimestep::new(UPDATE_INTERVAL_MS, Instant::now().as_millis());
+    #[cfg(feature = "fps")]
+    let mut frame_stats = FrameStats::new(Instant::now().as_millis());
+
     loop {
-        if game.state == GameState::GameOver {
-            if button.is_low() {
-                clicked_count += 1;
+        let now = Instant::now().as_millis();
+        let events = [
+            button_input.update(button.is_low(), now),
+            duck_input.update(duck_button.is_low()),
+        ];
+        for event in events.into_iter().flatten() {
+            last_input_ms = now;
+            if demo.take().is_some() {
+                // Any button ends the demo, the press itself is swallowed
+                info!("Demo over");
+                game.set_high_scores_enabled(true);
+                game.main_menu();
+                continue;
             }
-            if clicked_count > 2 {
-                clicked_count = 0;
-                game = Game::new(game.obstacles.rng, game.display);
-                Timer::after_millis(500).await;
+            match replay {
+                // Live input is ignored while a replay is running
+                Some(_) => (),
+                // The duck button on the game over screen replays the last run
+                None if game.state == GameState::GameOver && event == InputEvent::DuckStart => {
+                    if let Some(recording) = recorder.recording() {
+                        info!("Replaying the last run");
+                        replay = Some(Replay::start(&mut game, recording));
+                    }
+                }
+                None => recorder.handle_input(&mut game, event),
             }
-            Timer::after_millis(50).await;
-            continue;
         }
 
-        game.clear_screen().unwrap();
-        game.draw_score().unwrap();
+        let idle = matches!(game.state, GameState::MainMenu | GameState::GameOver);
+        if idle && demo.is_none() && replay.is_none() && now - last_input_ms >= DEMO_IDLE_MS {
+            info!("Starting the demo");
+            game.set_high_scores_enabled(false);
+            demo = Some(Autopilot::new());
+        }
+
+        let was_over = game.state == GameState::GameOver;
+        for _ in 0..timestep.advance(now) {
+            if let Some(event) = demo.as_mut().and_then(|autopilot| autopilot.update(&game)) {
+                game.handle_input(event);
+            }
+            if let (Some(replay), Some(recording)) = (replay.as_mut(), recorder.recording()) {
+                replay.feed(&mut game, recording);
+            }
+            game.step();
+        }
+        if !was_over && game.state == GameState::GameOver {
+            match replay.take() {
+                Some(replay) => replay.finish(&mut game),
+                // Dumped so the run can be saved as a replay fixture
+                None if demo.is_some() => (),
+                None => {
+                    if let Some(recording) = recorder.recording() {
+                        info!(
+                            "Run over, seed {=u32:#x} log {=[u8]:#x}",
+                            recording.seed(),
+                            recording.log()
+                        );
+                    }
+                }
+            }
+        }
 
-        if button.is_low() {
-            game.trex_jump();
+        while let Some(event) = game.take_sound() {
+            // The demo plays silently. When the buzzer is behind, the effect is
+            // dropped rather than holding the game loop.
+            if demo.is_none() {
+                SOUND_EVENTS.try_send(event).ok();
+            }
         }
 
-        game.move_world().unwrap();
-        game.draw_ground().unwrap();
-        game.draw_trex().unwrap();
+        if game.render().unwrap() {
+            for span in game.take_dirty_pages().spans() {
+                screen.draw_span(&span, game.display.span_data(&span));
+            }
 
-        if game.check_collison() {
-            game.game_over().unwrap();
-            game.display.flush().unwrap();
-            Timer::after_millis(500).await;
-            continue;
+            #[cfg(feature = "fps")]
+            if let Some(report) = frame_stats.frame_rendered(now, Instant::now().as_millis()) {
+                info!("{} fps, {} ms per frame", report.fps, report.frame_time_ms);
+            }
         }
 
-        game.display.flush().unwrap();
-        Timer::after_millis(5).await;
+        // Keep polling the buttons while waiting for the next update
+        Timer::after_millis(timestep.until_next_step_ms().min(5)).await;
     }
 }
 
-// Helper struct for Random number generation
-struct RandomGen {
-    rng: esp_hal::rng::Rng,
+/// Plays the sound effects on the buzzer, one after the other.
+#[embassy_executor::task]
+async fn sound_task(ledc: LEDC<'static>, buzzer_pin: GPIO33<'static>) {
+    let ledc = Ledc::new(ledc);
+    let mut hstimer0 = ledc.timer::<HighSpeed>(timer::Number::Timer0);
+    hstimer0.configure(tone_timer_config(440)).unwrap();
+
+    let mut channel0 = ledc.channel(channel::Number::Channel0, buzzer_pin);
+    channel0
+        .configure(channel::config::Config {
+            timer: &hstimer0,
+            duty_pct: 0, // Silent until the first tone
+            pin_config: channel::config::PinConfig::PushPull,
+        })
+        .unwrap();
+
+    loop {
+        let event = SOUND_EVENTS.receive().await;
+        for tone in sound::effect(event) {
+            if tone.freq_hz == 0 {
+                channel0.set_duty(0).unwrap();
+            } else {
+                // The channel holds on to `hstimer0`, retune the same hardware timer
+                let mut retune = ledc.timer::<HighSpeed>(timer::Number::Timer0);
+                retune.configure(tone_timer_config(tone.freq_hz)).unwrap();
+                channel0.set_duty(50).unwrap();
+            }
+            Timer::after_millis(tone.duration_ms.into()).await;
+        }
+        channel0.set_duty(0).unwrap();
+    }
 }
 
-impl RandomGen {
-    fn new(rng: esp_hal::rng::Rng) -> Self {
-        Self { rng }
+fn tone_timer_config(freq_hz: u32) -> timer::config::Config<timer::HSClockSource> {
+    timer::config::Config {
+        duty: timer::config::Duty::Duty10Bit,
+        clock_source: timer::HSClockSource::APBClk,
+        frequency: Rate::from_hz(freq_hz),
+    }
+}
+
+#[cfg(feature = "ssd1306-128x32")]
+type PanelSize = ssd1306::size::DisplaySize128x32;
+#[cfg(feature = "ssd1306-128x32")]
+const PANEL_SIZE: PanelSize = ssd1306::size::DisplaySize128x32;
+#[cfg(not(any(feature = "ssd1306-128x32", feature = "sh1106")))]
+type PanelSize = ssd1306::size::DisplaySize128x64;
+#[cfg(not(any(feature = "ssd1306-128x32", feature = "sh1106")))]
+const PANEL_SIZE: PanelSize = ssd1306::size::DisplaySize128x64;
+
+// SSD1306 module, the spans are written through its horizontal addressing mode
+#[cfg(not(feature = "sh1106"))]
+struct Screen {
+    display: Ssd1306<I2CInterface<I2c<'static, Async>>, PanelSize, BasicMode>,
+}
+
+#[cfg(not(feature = "sh1106"))]
+impl Screen {
+    fn new(i2c: I2c<'static, Async>) -> Self {
+        let interface = I2CDisplayInterface::new(i2c);
+        let mut display = Ssd1306::new(interface, PANEL_SIZE, DisplayRotation::Rotate0);
+        display.init().unwrap();
+        Self { display }
+    }
+
+    fn draw_span(&mut self, span: &PageSpan, data: &[u8]) {
+        let (start, end) = span.draw_area();
+        self.display.set_draw_area(start, end).unwrap();
+        self.display.draw(data).unwrap();
     }
 }
 
-impl jump_game::rng::Rng for RandomGen {
-    fn random_u32(&mut self) -> u32 {
-        self.rng.random()
+// SH1106 module, 128x64 as well. It only has the page addressing mode, so each
+// span is sent as a page write starting at its first column.
+#[cfg(feature = "sh1106")]
+struct Screen {
+    i2c: I2c<'static, Async>,
+}
+
+#[cfg(feature = "sh1106")]
+impl Screen {
+    const ADDRESS: u8 = 0x3C;
+    // The panel shows the columns 2 to 129 of the 132 of the controller
+    const COLUMN_OFFSET: u8 = 2;
+
+    fn new(i2c: I2c<'static, Async>) -> Self {
+        let mut screen = Self { i2c };
+        screen.command(&[
+            0xAE, // Display off
+            0xD5, 0x80, // Clock divider
+            0xA8, 0x3F, // 64 rows
+            0xD3, 0x00, // No vertical offset
+            0x40, // Start line 0
+            0xAD, 0x8B, // Charge pump on
+            0xA1, // Columns mirrored
+            0xC8, // Rows mirrored
+            0xDA, 0x12, // COM pins
+            0x81, 0x80, // Contrast
+            0xD9, 0x22, // Pre-charge period
+            0xDB, 0x35, // VCOM deselect level
+            0xA4, // Show the memory content
+            0xA6, // Not inverted
+            0xAF, // Display on
+        ]);
+        screen
+    }
+
+    fn command(&mut self, commands: &[u8]) {
+        // A control byte of 0 makes the following bytes commands
+        let mut bytes = [0; 32];
+        bytes[1..=commands.len()].copy_from_slice(commands);
+        self.i2c
+            .write(Self::ADDRESS, &bytes[..=commands.len()])
+            .unwrap();
+    }
+
+    fn draw_span(&mut self, span: &PageSpan, data: &[u8]) {
+        let column = span.first_column + Self::COLUMN_OFFSET;
+        self.command(&[0xB0 | span.page, column & 0x0F, 0x10 | (column >> 4)]);
+        // A control byte of 0x40 makes the following bytes display data
+        let mut bytes = [0x40; 129];
+        bytes[1..=data.len()].copy_from_slice(data);
+        self.i2c.write(Self::ADDRESS, &bytes[..=data.len()]).unwrap();
+    }
+}
+
+// Label of the partition holding the high scores, a data partition of subtype
+// 0x40 in partitions.csv
+const SCORES_PARTITION: &str = "scores";
+
+// High-score table kept in its own flash partition
+struct FlashScoreStorage {
+    flash: FlashStorage,
+    // Of the partition, read from the partition table
+    offset: u32,
+}
+
+impl FlashScoreStorage {
+    // Returns `None` when the partition table has no partition big enough for
+    // the table
+    fn new(mut flash: FlashStorage) -> Option<Self> {
+        let mut buffer = [0; partitions::PARTITION_TABLE_MAX_LEN];
+        let table = partitions::read_partition_table(&mut flash, &mut buffer).ok()?;
+        let partition = (0..table.len())
+            .filter_map(|index| table.get_partition(index).ok())
+            .find(|partition| partition.label_as_str() == SCORES_PARTITION)?;
+        if partition.len() < highscore::ENCODED_SIZE as u32 {
+            return None;
+        }
+        let offset = partition.offset();
+        Some(Self { flash, offset })
+    }
+}
+
+impl ScoreStorage for FlashScoreStorage {
+    type Error = esp_storage::FlashStorageError;
+
+    fn load(&mut self) -> Result<Scores, Self::Error> {
+        let mut bytes = [0; highscore::ENCODED_SIZE];
+        self.flash.read(self.offset, &mut bytes)?;
+        Ok(highscore::decode(&bytes))
+    }
+
+    fn save(&mut self, scores: &Scores) -> Result<(), Self::Error> {
+        self.flash.write(self.offset, &highscore::encode(scores))
+    }
+}
+
+// The flash partition, or RAM when the flash has none
+enum BoardScoreStorage {
+    Flash(FlashScoreStorage),
+    Memory(MemoryStorage),
+}
+
+impl ScoreStorage for BoardScoreStorage {
+    type Error = esp_storage::FlashStorageError;
+
+    fn load(&mut self) -> Result<Scores, Self::Error> {
+        match self {
+            Self::Flash(flash) => flash.load(),
+            Self::Memory(memory) => memory.load().map_err(|never| match never {}),
+        }
+    }
+
+    fn save(&mut self, scores: &Scores) -> Result<(), Self::Error> {
+        match self {
+            Self::Flash(flash) => flash.save(scores),
+            Self::Memory(memory) => memory.save(scores).map_err(|never| match never {}),
+        }
     }
 }
 
@@ -0,0 +1,105 @@
+use crate::sprites::{OBSTACLE_MAX_WIDTH, TREX_GROUND_Y, TREX_MIN_Y, TREX_VELOCITY};
+
+/// Parameters of the difficulty curve.
+///
+/// The obstacle speed grows by `speed_step` every `points_per_step` points until it
+/// reaches `max_speed`. Gaps between obstacles are picked at random in
+/// `min_gap..=max_gap`, but never shorter than the distance needed to land from a jump.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyConfig {
+    /// Obstacle speed at the start of a run, in pixels per frame.
+    pub start_speed: i32,
+    pub max_speed: i32,
+    pub speed_step: i32,
+    pub points_per_step: u32,
+    /// Distance between the left edges of two obstacles, in pixels.
+    pub min_gap: i32,
+    pub max_gap: i32,
+    /// Falling speed of the T-Rex, in pixels per frame.
+    pub gravity: i32,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            start_speed: 12,
+            max_speed: 20,
+            speed_step: 1,
+            points_per_step: 10,
+            min_gap: 100,
+            max_gap: 160,
+            gravity: 7,
+        }
+    }
+}
+
+impl DifficultyConfig {
+    /// Obstacle speed for the given score.
+    pub fn speed(&self, score: u32) -> i32 {
+        let steps = score / self.points_per_step.max(1);
+        let speed = self
+            .start_speed
+            .saturating_add(self.speed_step.saturating_mul(steps as i32));
+        speed.min(self.max_speed)
+    }
+
+    /// Obstacle velocity for the given score, negative as the obstacles move to the left.
+    pub fn velocity(&self, score: u32) -> i32 {
+        -self.speed(score)
+    }
+
+    /// Number of frames the T-Rex spends in the air during a full jump.
+    pub fn jump_frames(&self) -> i32 {
+        let height = TREX_GROUND_Y - TREX_MIN_Y;
+        div_ceil(height, -TREX_VELOCITY) + div_ceil(height, self.gravity.max(1))
+    }
+
+    /// Shortest gap that can be jumped at the given speed.
+    ///
+    /// While the T-Rex is in the air the next obstacle must not reach the place
+    /// of the one being jumped over, otherwise it would land on it.
+    pub fn min_jumpable_gap(&self, speed: i32) -> i32 {
+        self.jump_frames() * speed + OBSTACLE_MAX_WIDTH
+    }
+
+    /// Range the gaps are picked from at the given speed.
+    pub fn gap_range(&self, speed: i32) -> (i32, i32) {
+        let min = self.min_gap.max(self.min_jumpable_gap(speed));
+        (min, self.max_gap.max(min))
+    }
+}
+
+fn div_ceil(a: i32, b: i32) -> i32 {
+    (a + b - 1) / b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_grows_in_steps_up_to_the_max() {
+        let config = DifficultyConfig::default();
+        assert_eq!(config.speed(0), 12);
+        assert_eq!(config.speed(9), 12);
+        assert_eq!(config.speed(10), 13);
+        assert_eq!(config.speed(35), 15);
+        assert_eq!(config.speed(10_000), config.max_speed);
+        assert_eq!(config.velocity(10), -13);
+    }
+
+    #[test]
+    fn gaps_are_never_shorter_than_a_jump() {
+        let config = DifficultyConfig {
+            min_gap: 10,
+            max_gap: 20,
+            ..Default::default()
+        };
+        assert_eq!(config.jump_frames(), 8);
+        for speed in config.start_speed..=config.max_speed {
+            let (min, max) = config.gap_range(speed);
+            assert!(min >= config.jump_frames() * speed);
+            assert!(max >= min);
+        }
+    }
+}
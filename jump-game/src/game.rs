@@ -1,3 +1,8 @@
+use crate::difficulty::DifficultyConfig;
+use crate::highscore::{HighScores, ScoreStorage};
+use crate::input::InputEvent;
+use crate::layout::Layout;
+use crate::sound::{self, SoundEvent};
 use crate::sprites::{self, Ground, Obstacles, Trex, TrexState};
 use core::fmt::Write;
 use embedded_graphics::geometry::Dimensions;
@@ -7,145 +12,533 @@ use embedded_graphics::{
     pixelcolor::{self, BinaryColor},
     prelude::*,
     primitives::{PrimitiveStyle, Rectangle},
-    text::{Baseline, Text},
+    text::{Alignment, Baseline, Text},
 };
-use heapless::String;
+use heapless::{Deque, String, Vec};
+use oled_dirty::DirtyPages;
 
-const SCORE_BOARD_X: i32 = 60;
-const SCORE_BOARD_Y: i32 = 5;
+// Pages of 8 rows tracked for the flushes, enough for a 64 pixels high display
+pub const DISPLAY_PAGES: usize = 8;
 
-#[derive(PartialEq)]
+// High-score table on the game over screen, on the left of the score board
+const HIGH_SCORES_X: i32 = 2;
+const HIGH_SCORES_Y: i32 = 2;
+const HIGH_SCORES_LINE_HEIGHT: i32 = 10;
+
+// Baselines of the texts on a 64 pixels high display, see `Layout::text_y`
+const TITLE_Y: i32 = 20;
+const START_HINT_Y: i32 = 36;
+// Restart hint, below the game over image
+const RESTART_HINT_DY: i32 = 18;
+
+// Time between two updates of the game physics
+pub const UPDATE_INTERVAL_MS: u64 = 60;
+
+// How early a press made in the air is still turned into a jump on landing
+const JUMP_BUFFER_TICKS: u32 = 3;
+
+// Points between two switches from day to night and back, night has the colours inverted
+pub const DAY_NIGHT_POINTS: u32 = 200;
+
+// Sound effects waiting to be played, more than a few would be heard late anyway
+const SOUND_QUEUE_SIZE: usize = 4;
+
+// Updates to wait on the game over screen before a press restarts the game,
+// so the press that caused the crash doesn't skip the screen
+const GAME_OVER_COOLDOWN_TICKS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GameState {
-    // MainMenu,
+    MainMenu,
     Playing,
+    Paused,
     GameOver,
 }
 
-pub struct Game<D, R>
+pub struct Game<D, R, S>
 where
     D: DrawTarget<Color = pixelcolor::BinaryColor>,
     R: super::rng::Rng,
+    S: ScoreStorage,
 {
     pub state: GameState,
     pub display: D,
     pub obstacles: Obstacles<R>,
+    pub high_scores: HighScores<S>,
     score: u32,
+    // Rank of the last score in the high-score table, if it made it
+    new_high_score: Option<usize>,
     trex: Trex,
     ground: Ground,
+    layout: Layout,
     text_style: MonoTextStyle<'static, BinaryColor>,
+    highlight_style: MonoTextStyle<'static, BinaryColor>,
+    // Updates spent in the current state
+    state_ticks: u32,
+    // Updates played since the start of the run, pauses excluded
+    run_ticks: u32,
+    // Whether the jump button is down, a buffered jump is a hop once it's released
+    jump_held: bool,
+    // Update of a press made in the air, waiting for the landing
+    buffered_jump: Option<u32>,
+    // Cleared while replaying so replays don't end up in the high-score table
+    high_scores_enabled: bool,
+    // Set when something changed since the last render
+    redraw: bool,
+    // Set when the next frame must be drawn from a blank screen
+    full_redraw: bool,
+    // Areas of the T-Rex and the obstacles in the last frame, erased by the next one.
+    // The obstacle queue holds one less than its size, which leaves room for the T-Rex
+    sprite_areas: Vec<Rectangle, { sprites::BUFF_SIZE }>,
+    // Score shown on the screen, to only draw it again when it changes
+    score_drawn: Option<u32>,
+    // Parts of the display changed since the last flush
+    dirty: DirtyPages<DISPLAY_PAGES>,
+    // Set at night, everything is drawn with the colours inverted
+    night: bool,
+    // Sound effects not played yet
+    sounds: Deque<SoundEvent, SOUND_QUEUE_SIZE>,
 }
 
-impl<D, R> Game<D, R>
+impl<D, R, S> Game<D, R, S>
 where
     D: DrawTarget<Color = pixelcolor::BinaryColor>,
     R: super::rng::Rng,
+    S: ScoreStorage,
 {
-    pub fn new(rng: R, display: D) -> Self {
+    pub fn new(rng: R, display: D, storage: S, difficulty: DifficultyConfig) -> Self {
         let text_style = MonoTextStyleBuilder::new()
             .font(&FONT_6X10)
             .text_color(BinaryColor::On)
             .build();
+        let highlight_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::Off)
+            .background_color(BinaryColor::On)
+            .build();
+        let display_size = display.bounding_box().size;
+        let layout = Layout::new(display_size);
         Self {
             score: 0,
+            new_high_score: None,
+            high_scores: HighScores::new(storage),
             display,
             text_style,
-            trex: Trex::new(sprites::TREX_X, sprites::TREX_GROUND_Y),
-            ground: Ground::default(),
-            obstacles: Obstacles::new(rng),
-            // TODO: Start with GameState::MainMenu
-            state: GameState::Playing,
+            highlight_style,
+            trex: Trex::for_layout(&layout, difficulty.gravity),
+            ground: Ground::new(&layout),
+            obstacles: Obstacles::new(rng, difficulty, layout),
+            layout,
+            state: GameState::MainMenu,
+            state_ticks: 0,
+            run_ticks: 0,
+            jump_held: false,
+            buffered_jump: None,
+            high_scores_enabled: true,
+            redraw: true,
+            full_redraw: true,
+            sprite_areas: Vec::new(),
+            score_drawn: None,
+            dirty: DirtyPages::new(display_size),
+            night: false,
+            sounds: Deque::new(),
+        }
+    }
+
+    /// Applies a button event to the state machine.
+    ///
+    /// * Main menu: a press starts the game.
+    /// * Playing: a press jumps, higher the longer the button is held. A press just
+    ///   before landing jumps again on touchdown. A long press pauses, holding the
+    ///   duck button ducks.
+    /// * Paused: a press resumes.
+    /// * Game over: once the cooldown is over, a press starts a new game.
+    pub fn handle_input(&mut self, event: InputEvent) {
+        match (self.state, event) {
+            (GameState::MainMenu, InputEvent::Press) => self.restart(),
+            (GameState::Playing, InputEvent::Press) => {
+                self.jump_held = true;
+                if self.trex_jump() {
+                    self.redraw = true;
+                } else if matches!(self.trex.state, TrexState::Jumping | TrexState::Falling) {
+                    self.buffered_jump = Some(self.run_ticks);
+                }
+            }
+            (_, InputEvent::Release) => {
+                self.jump_held = false;
+                self.trex.release_jump();
+            }
+            (GameState::Playing, InputEvent::LongPress) => self.set_state(GameState::Paused),
+            (GameState::Playing, InputEvent::DuckStart) => {
+                self.trex.duck(true);
+                self.redraw = true;
+            }
+            // Released while paused or in a menu, the T-Rex must not stay ducked
+            (_, InputEvent::DuckEnd) => self.trex.duck(false),
+            (GameState::Paused, InputEvent::Press) => self.set_state(GameState::Playing),
+            (GameState::GameOver, InputEvent::Press)
+                if self.state_ticks >= GAME_OVER_COOLDOWN_TICKS =>
+            {
+                self.restart();
+            }
+            _ => (),
+        }
+    }
+
+    /// Advances the game by one update, without drawing anything.
+    ///
+    /// Runs every `UPDATE_INTERVAL_MS` whatever the time taken by rendering.
+    pub fn step(&mut self) {
+        self.state_ticks = self.state_ticks.saturating_add(1);
+
+        match self.state {
+            GameState::Playing => {
+                self.run_ticks += 1;
+                self.move_world();
+                self.trex.update_state();
+                self.land_buffered_jump();
+                if self.check_collison() {
+                    self.game_over();
+                }
+                self.redraw = true;
+            }
+            GameState::GameOver if self.state_ticks == GAME_OVER_COOLDOWN_TICKS => {
+                // Time to show the restart hint
+                self.redraw = true;
+            }
+            _ => (),
         }
     }
 
-    pub fn move_world(&mut self) -> Result<(), D::Error> {
-        if self.obstacles.update_state() {
+    /// Draws the current state if it changed since the last call.
+    ///
+    /// Returns `true` when the display changed and needs to be flushed.
+    pub fn render(&mut self) -> Result<bool, D::Error> {
+        if !self.redraw {
+            return Ok(false);
+        }
+        self.redraw = false;
+
+        match self.state {
+            GameState::MainMenu => self.draw_main_menu()?,
+            GameState::Playing => self.draw_frame()?,
+            // Drawn over the last frame of the game
+            GameState::Paused => self.draw_paused()?,
+            GameState::GameOver => {
+                self.draw_game_over()?;
+                if self.state_ticks >= GAME_OVER_COOLDOWN_TICKS {
+                    self.draw_restart_hint()?;
+                }
+            }
+        }
+        // Screens other than the game itself are drawn rarely, they are sent whole
+        if self.state != GameState::Playing {
+            self.dirty.mark_all();
+            self.full_redraw = true;
+        }
+        Ok(true)
+    }
+
+    /// Parts of the display changed by `render` since the last call.
+    ///
+    /// Only these have to be sent to the display.
+    pub fn take_dirty_pages(&mut self) -> DirtyPages<DISPLAY_PAGES> {
+        self.dirty.take()
+    }
+
+    /// Makes the next `render` draw everything, e.g. after the display lost its content.
+    pub fn redraw_all(&mut self) {
+        self.redraw = true;
+        self.full_redraw = true;
+    }
+
+    /// Runs one update and draws the result, see `step` and `render`.
+    pub fn update(&mut self) -> Result<bool, D::Error> {
+        self.step();
+        self.render()
+    }
+
+    /// Starts a new run, reusing the display and the random generator.
+    pub fn restart(&mut self) {
+        self.score = 0;
+        self.run_ticks = 0;
+        self.new_high_score = None;
+        self.buffered_jump = None;
+        self.night = false;
+        self.trex = Trex::for_layout(&self.layout, self.obstacles.difficulty.gravity);
+        self.ground = Ground::new(&self.layout);
+        self.obstacles.reset();
+        self.set_state(GameState::Playing);
+    }
+
+    /// Drops the current run and goes back to the main menu.
+    pub fn main_menu(&mut self) {
+        self.night = false;
+        self.set_state(GameState::MainMenu);
+    }
+
+    fn set_state(&mut self, state: GameState) {
+        self.state = state;
+        self.state_ticks = 0;
+        self.redraw_all();
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    /// Placement of the game on the display, computed from its size.
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Updates played since the start of the run, the time base of the replays.
+    pub fn run_ticks(&self) -> u32 {
+        self.run_ticks
+    }
+
+    /// Enables or disables recording the score of finished runs in the high-score table.
+    pub fn set_high_scores_enabled(&mut self, enabled: bool) {
+        self.high_scores_enabled = enabled;
+    }
+
+    /// Rank of the last finished run in the high-score table, `Some(0)` is a new record.
+    pub fn new_high_score(&self) -> Option<usize> {
+        self.new_high_score
+    }
+
+    pub fn move_world(&mut self) {
+        let velocity = self.obstacles.difficulty.velocity(self.score);
+        if self.obstacles.update_state(velocity) {
             self.score += 1;
+            if self.score % sound::MILESTONE_POINTS == 0 {
+                self.play(SoundEvent::Milestone);
+            }
+            if self.score % DAY_NIGHT_POINTS == 0 {
+                // The whole screen changes colour
+                self.night = !self.night;
+                self.full_redraw = true;
+            }
+        }
+        self.ground.move_by_velocity(velocity);
+    }
+
+    /// Draws a frame of the run, only touching what may have changed since the last one.
+    ///
+    /// The sprites of the last frame are erased and drawn at their new place, the
+    /// ground moves every frame and the score is drawn again when it changed.
+    pub fn draw_frame(&mut self) -> Result<(), D::Error> {
+        let score_area = self.layout.score_board;
+        let mut score_erased = false;
+        if self.full_redraw {
+            self.full_redraw = false;
+            Inverted::new(&mut self.display, self.night).clear(BinaryColor::Off)?;
+            self.dirty.mark_all();
+            self.sprite_areas.clear();
+            score_erased = true;
+        }
+        for area in core::mem::take(&mut self.sprite_areas) {
+            self.erase(area)?;
+            score_erased |= !area.intersection(&score_area).is_zero_sized();
+        }
+
+        if score_erased || self.score_drawn != Some(self.score) {
+            self.draw_score()?;
         }
-        self.ground.move_by_velocity(sprites::OBSTACLE_VELOCITY);
         self.draw_obstacles()?;
+        self.draw_ground()?;
+        self.draw_trex()?;
         Ok(())
     }
 
-    pub fn draw_obstacles(&mut self) -> Result<(), D::Error> {
-        for obs in self.obstacles.get_current().iter() {
-            obs.img.draw(&mut self.display)?;
-        }
+    fn erase(&mut self, area: Rectangle) -> Result<(), D::Error> {
+        area.into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        self.dirty.mark(&area);
         Ok(())
     }
 
-    pub fn clear_screen(&mut self) -> Result<(), D::Error> {
-        let text_area = Rectangle::new(Point::new(0, self.trex.position.y), Size::new(128, 64));
-        text_area
-            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
-            .draw(&mut self.display)?;
+    pub fn draw_obstacles(&mut self) -> Result<(), D::Error> {
+        for obs in self.obstacles.get_current().iter() {
+            obs.img
+                .draw(&mut Inverted::new(&mut self.display, self.night))?;
+            let area = obs.img.bounding_box();
+            self.dirty.mark(&area);
+            self.sprite_areas.push(area).ok();
+        }
         Ok(())
     }
 
     pub fn draw_score(&mut self) -> Result<(), D::Error> {
-        let text_area =
-            Rectangle::new(Point::new(SCORE_BOARD_X, SCORE_BOARD_Y), Size::new(128, 64));
-        text_area
-            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
-            .draw(&mut self.display)?;
+        let text_area = self.layout.score_board;
+        self.erase(text_area)?;
+        self.score_drawn = Some(self.score);
 
         let mut buff: String<32> = String::new();
         write!(buff, "Score: {}", self.score).unwrap();
-        Text::with_baseline(
-            &buff,
-            Point::new(SCORE_BOARD_X, SCORE_BOARD_Y),
-            self.text_style,
-            Baseline::Top,
-        )
-        .draw(&mut self.display)?;
+        Text::with_baseline(&buff, text_area.top_left, self.text_style, Baseline::Top)
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
         Ok(())
     }
 
     pub fn draw_game_over(&mut self) -> Result<(), D::Error> {
-        self.clear_screen()?;
-        Image::new(&sprites::RAW_GAME_OVER, Point::new(16, 32)).draw(&mut self.display)?;
+        Inverted::new(&mut self.display, self.night).clear(BinaryColor::Off)?;
+        let game_over_y = self.layout.game_over_y;
+        Image::new(&sprites::RAW_GAME_OVER, Point::new(16, game_over_y))
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        // Final score stays on the top of the screen
+        self.draw_score()?;
+        self.draw_high_scores()?;
+        let record = self.layout.score_board.top_left + Point::new(0, HIGH_SCORES_LINE_HEIGHT);
+        if self.new_high_score == Some(0) && record.y + HIGH_SCORES_LINE_HEIGHT <= game_over_y {
+            Text::with_baseline("NEW RECORD", record, self.highlight_style, Baseline::Top)
+                .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        }
         Ok(())
     }
 
-    pub fn draw_trex(&mut self) -> Result<(), D::Error> {
-        if self.trex.state != TrexState::Running {
-            self.trex.update_state();
+    /// Draws the high-score table, highlighting the entry of the last run.
+    ///
+    /// Only the lines that fit above the game over image are drawn.
+    pub fn draw_high_scores(&mut self) -> Result<(), D::Error> {
+        let scores = *self.high_scores.scores();
+        for (rank, score) in scores.iter().enumerate() {
+            let y = HIGH_SCORES_Y + rank as i32 * HIGH_SCORES_LINE_HEIGHT;
+            if y + HIGH_SCORES_LINE_HEIGHT > self.layout.game_over_y {
+                break;
+            }
+            let mut buff: String<16> = String::new();
+            write!(buff, "{}.{}", rank + 1, score).unwrap();
+
+            let style = if self.new_high_score == Some(rank) {
+                self.highlight_style
+            } else {
+                self.text_style
+            };
+            Text::with_baseline(&buff, Point::new(HIGH_SCORES_X, y), style, Baseline::Top)
+                .draw(&mut Inverted::new(&mut self.display, self.night))?;
         }
+        Ok(())
+    }
+
+    pub fn draw_main_menu(&mut self) -> Result<(), D::Error> {
+        Inverted::new(&mut self.display, self.night).clear(BinaryColor::Off)?;
+        self.draw_centered_text("JUMP GAME", self.layout.text_y(TITLE_Y))?;
+        self.draw_centered_text("Press to start", self.layout.text_y(START_HINT_Y))?;
+        self.draw_ground()?;
+        self.draw_trex()?;
+        Ok(())
+    }
+
+    pub fn draw_paused(&mut self) -> Result<(), D::Error> {
+        self.draw_centered_text("PAUSED", self.layout.text_y(TITLE_Y))?;
+        Ok(())
+    }
 
-        self.trex.img.draw(&mut self.display)?;
+    pub fn draw_restart_hint(&mut self) -> Result<(), D::Error> {
+        let y = self.layout.game_over_y + RESTART_HINT_DY;
+        self.draw_centered_text("Press to restart", y)?;
+        Ok(())
+    }
 
+    fn draw_centered_text(&mut self, text: &str, y: i32) -> Result<(), D::Error> {
+        let x = self.display.bounding_box().center().x;
+        Text::with_alignment(text, Point::new(x, y), self.text_style, Alignment::Center)
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        Ok(())
+    }
+
+    pub fn draw_trex(&mut self) -> Result<(), D::Error> {
+        self.trex
+            .img
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        let area = self.trex.img.bounding_box();
+        self.dirty.mark(&area);
+        self.sprite_areas.push(area).ok();
         Ok(())
     }
 
     pub fn draw_ground(&mut self) -> Result<(), D::Error> {
-        self.ground.img.draw(&mut self.display)?;
+        self.ground
+            .img
+            .draw(&mut Inverted::new(&mut self.display, self.night))?;
+        // Covers the whole width, it is drawn over instead of erased
+        self.dirty.mark(&self.ground.img.bounding_box());
         Ok(())
     }
 
     pub fn trex_jump(&mut self) -> bool {
-        if self.trex.state == TrexState::Running {
-            self.trex.state = TrexState::Jumping;
-            self.trex.update_state();
+        if self.trex.jump() {
+            self.play(SoundEvent::Jump);
             return true;
         }
         false
     }
 
+    // Jumps on touchdown when the button was pressed shortly before
+    fn land_buffered_jump(&mut self) {
+        let Some(pressed_at) = self.buffered_jump else {
+            return;
+        };
+        if self.run_ticks - pressed_at > JUMP_BUFFER_TICKS {
+            self.buffered_jump = None;
+        } else if self.trex.state == TrexState::Running {
+            self.buffered_jump = None;
+            self.trex_jump();
+            if !self.jump_held {
+                self.trex.release_jump();
+            }
+        }
+    }
+
+    /// Checks the bounding boxes first, then the masks so that only visible pixels collide.
     pub fn check_collison(&mut self) -> bool {
-        let trex_bbox = self.trex.img.bounding_box();
+        let trex_bbox = self.trex.hitbox();
+        let trex_mask = self.trex.mask();
 
         for obs in self.obstacles.get_current().iter() {
             let obs_bbox = obs.img.bounding_box();
-            if bounding_boxes_overlap(trex_bbox, obs_bbox) {
+            if bounding_boxes_overlap(trex_bbox, obs_bbox)
+                && sprites::masks_overlap(
+                    trex_mask,
+                    trex_bbox.top_left,
+                    obs.mask,
+                    obs_bbox.top_left,
+                )
+            {
                 return true;
             }
         }
         false
     }
 
-    pub fn game_over(&mut self) -> Result<(), D::Error> {
-        self.state = GameState::GameOver;
-        self.draw_game_over()?;
-        Ok(())
+    pub fn game_over(&mut self) {
+        self.set_state(GameState::GameOver);
+        self.play(SoundEvent::Collision);
+        if !self.high_scores_enabled {
+            return;
+        }
+        self.new_high_score = self.high_scores.insert(self.score);
+        if self.new_high_score.is_some() {
+            // A failed write only loses the table on the next reset, keep playing
+            self.high_scores.save().ok();
+        }
+        if self.new_high_score == Some(0) {
+            self.play(SoundEvent::NewHighScore);
+        }
+    }
+
+    /// Next sound effect to play, in the order they happened.
+    pub fn take_sound(&mut self) -> Option<SoundEvent> {
+        self.sounds.pop_front()
+    }
+
+    fn play(&mut self, event: SoundEvent) {
+        // Nobody listens to the queue on the host, the oldest effects are dropped
+        if self.sounds.is_full() {
+            self.sounds.pop_front();
+        }
+        self.sounds.push_back(event).ok();
     }
 }
 
@@ -176,3 +569,328 @@ fn bounding_boxes_overlap(bbox1: Rectangle, bbox2: Rectangle) -> bool {
         false
     }
 }
+
+// Draws on `display` with the colours swapped when `invert` is set
+struct Inverted<'a, D> {
+    display: &'a mut D,
+    invert: bool,
+}
+
+impl<'a, D> Inverted<'a, D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn new(display: &'a mut D, invert: bool) -> Self {
+        Self { display, invert }
+    }
+
+    fn paint(invert: bool, color: BinaryColor) -> BinaryColor {
+        if invert {
+            color.invert()
+        } else {
+            color
+        }
+    }
+}
+
+impl<D> Dimensions for Inverted<'_, D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.display.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Inverted<'_, D>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    type Color = BinaryColor;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let invert = self.invert;
+        self.display.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point, Self::paint(invert, color))),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.display
+            .fill_solid(area, Self::paint(self.invert, color))
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.clear(Self::paint(self.invert, color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::Autopilot;
+    use crate::difficulty::DifficultyConfig;
+    use crate::highscore::MemoryStorage;
+    use crate::rng::SeededRng;
+    use crate::sim::{self, SimGame};
+    use crate::sprites::{CollisionMask, Obstacle};
+    use embedded_graphics::image::ImageRaw;
+    use oled_dirty::PageBuffer;
+
+    // Solid 40x40 block, wide enough to cover the T-Rex
+    const RAW_WALL: ImageRaw<'static, BinaryColor> = ImageRaw::new(&[0xFF; 5 * 40], 40);
+    const MASK_WALL: CollisionMask = CollisionMask::new(&[0xFF; 5 * 40], 40);
+
+    fn place_obstacle(game: &mut SimGame, obstacle: Obstacle) {
+        while game.obstacles.buffer.dequeue().is_some() {}
+        game.obstacles.buffer.enqueue(obstacle).unwrap();
+    }
+
+    fn playing_game() -> SimGame {
+        let mut game = sim::new_game(3, DifficultyConfig::default());
+        game.handle_input(InputEvent::Press);
+        game
+    }
+
+    #[test]
+    fn overlapping_boxes_collide() {
+        let a = Rectangle::new(Point::new(0, 0), Size::new(10, 10));
+        let b = Rectangle::new(Point::new(5, 5), Size::new(10, 10));
+        assert!(bounding_boxes_overlap(a, b));
+        assert!(bounding_boxes_overlap(b, a));
+    }
+
+    #[test]
+    fn distant_boxes_do_not_collide() {
+        let a = Rectangle::new(Point::new(0, 0), Size::new(10, 10));
+        assert!(!bounding_boxes_overlap(
+            a,
+            Rectangle::new(Point::new(20, 0), Size::new(5, 5))
+        ));
+        assert!(!bounding_boxes_overlap(
+            a,
+            Rectangle::new(Point::new(0, 20), Size::new(5, 5))
+        ));
+    }
+
+    #[test]
+    fn boxes_sharing_an_edge_do_not_collide() {
+        let a = Rectangle::new(Point::new(0, 0), Size::new(10, 10));
+        let b = Rectangle::new(Point::new(9, 0), Size::new(10, 10));
+        assert!(!bounding_boxes_overlap(a, b));
+    }
+
+    #[test]
+    fn empty_box_never_collides() {
+        let a = Rectangle::new(Point::new(0, 0), Size::zero());
+        let b = Rectangle::new(Point::new(0, 0), Size::new(10, 10));
+        assert!(!bounding_boxes_overlap(a, b));
+    }
+
+    #[test]
+    fn obstacle_on_the_trex_is_a_collision() {
+        let mut game = playing_game();
+        assert!(!game.check_collison());
+
+        let trex = game.trex.hitbox().top_left;
+        place_obstacle(
+            &mut game,
+            Obstacle::new(&RAW_WALL, &MASK_WALL, trex.x, trex.y),
+        );
+        assert!(game.check_collison());
+    }
+
+    #[test]
+    fn transparent_corners_do_not_collide() {
+        let mut game = playing_game();
+        // Only the empty top left corner of the T-Rex touches the bottom of the wall
+        let trex = game.trex.hitbox().top_left;
+        let wall = Obstacle::new(&RAW_WALL, &MASK_WALL, trex.x - 38, trex.y - 38);
+        assert!(bounding_boxes_overlap(
+            game.trex.hitbox(),
+            wall.img.bounding_box()
+        ));
+
+        place_obstacle(&mut game, wall);
+        assert!(!game.check_collison());
+    }
+
+    #[test]
+    fn score_counts_obstacles_leaving_the_screen() {
+        let mut game = playing_game();
+        let mut frames = 0;
+        while game.score() == 0 {
+            game.move_world();
+            frames += 1;
+            assert!(frames < 50, "no obstacle left the screen");
+        }
+        assert_eq!(game.score(), 1);
+        assert_eq!(game.obstacles.buffer.len(), 2);
+    }
+
+    #[test]
+    fn press_starts_and_long_press_pauses() {
+        let mut game = sim::new_game(3, DifficultyConfig::default());
+        assert_eq!(game.state, GameState::MainMenu);
+
+        game.handle_input(InputEvent::Press);
+        assert_eq!(game.state, GameState::Playing);
+
+        game.handle_input(InputEvent::LongPress);
+        assert_eq!(game.state, GameState::Paused);
+        game.update().unwrap();
+        assert_eq!(game.state, GameState::Paused);
+
+        game.handle_input(InputEvent::Press);
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn game_over_waits_before_restarting() {
+        let mut game = playing_game();
+        game.score = 12;
+        game.game_over();
+        assert_eq!(game.new_high_score(), Some(0));
+        assert_eq!(game.high_scores.best(), 12);
+
+        game.handle_input(InputEvent::Press);
+        assert_eq!(game.state, GameState::GameOver);
+
+        for _ in 0..GAME_OVER_COOLDOWN_TICKS {
+            game.update().unwrap();
+        }
+        game.handle_input(InputEvent::Press);
+        assert_eq!(game.state, GameState::Playing);
+        assert_eq!(game.score(), 0);
+        assert_eq!(game.new_high_score(), None);
+    }
+
+    #[test]
+    fn partial_frames_match_a_full_redraw() {
+        let mut game = playing_game();
+        let mut autopilot = Autopilot::new();
+        for _ in 0..300 {
+            if let Some(event) = autopilot.update(&game) {
+                game.handle_input(event);
+            }
+            game.update().unwrap();
+            let partial = game.display.clone();
+
+            game.redraw_all();
+            game.render().unwrap();
+            assert_eq!(game.display, partial);
+        }
+        assert_eq!(game.state, GameState::Playing);
+    }
+
+    #[test]
+    fn only_the_moving_parts_are_flushed() {
+        let mut game = playing_game();
+        game.render().unwrap();
+        assert_eq!(game.take_dirty_pages().byte_count(), 128 * 8);
+
+        game.update().unwrap();
+        let dirty = game.take_dirty_pages();
+        // The score board on the top didn't change
+        assert!(dirty.spans().all(|span| span.page >= 3));
+        assert!(dirty.byte_count() < 128 * 8 / 2);
+        assert!(game.take_dirty_pages().is_empty());
+    }
+
+    #[test]
+    fn short_display_fits_the_game() {
+        let mut game = Game::new(
+            SeededRng::new(3),
+            PageBuffer::<128, 4>::new(),
+            MemoryStorage::new(),
+            DifficultyConfig::default(),
+        );
+        assert_eq!(game.layout(), &Layout::new(Size::new(128, 32)));
+        game.handle_input(InputEvent::Press);
+        let mut autopilot = Autopilot::new();
+        for _ in 0..300 {
+            if let Some(event) = autopilot.update(&game) {
+                game.handle_input(event);
+            }
+            game.update().unwrap();
+            assert!(game.take_dirty_pages().spans().all(|span| span.page < 4));
+            // The T-Rex stays whole on the display, at the top of its jumps too
+            assert!(game.trex.hitbox().top_left.y >= 0);
+        }
+        assert_eq!(game.state, GameState::Playing);
+        assert!(game.score() > 0);
+
+        // The line of the ground is on the bottom row
+        let line_y = game.layout().ground_y + 4;
+        assert_eq!(line_y, 31);
+        let ground = (0..128)
+            .filter(|&x| game.display.pixel(Point::new(x, line_y)))
+            .count();
+        assert!(ground > 64);
+    }
+
+    #[test]
+    fn night_inverts_the_whole_screen() {
+        let mut game = playing_game();
+        game.update().unwrap();
+        assert!(!game.display.pixel(Point::zero()));
+
+        for (score, night) in [(DAY_NIGHT_POINTS, true), (2 * DAY_NIGHT_POINTS, false)] {
+            game.score = score - 1;
+            while game.score() < score {
+                game.move_world();
+            }
+            game.redraw = true;
+            game.render().unwrap();
+            assert_eq!(game.display.pixel(Point::zero()), night);
+            assert_eq!(game.take_dirty_pages().byte_count(), 128 * 8);
+
+            let frame = game.display.clone();
+            game.redraw_all();
+            game.render().unwrap();
+            assert_eq!(game.display, frame);
+        }
+    }
+
+    #[test]
+    fn game_events_queue_sound_effects() {
+        let mut game = playing_game();
+        assert!(game.trex_jump());
+        assert!(!game.trex_jump());
+        assert_eq!(game.take_sound(), Some(SoundEvent::Jump));
+        assert_eq!(game.take_sound(), None);
+
+        game.score = sound::MILESTONE_POINTS - 1;
+        while game.score() < sound::MILESTONE_POINTS {
+            game.move_world();
+        }
+        assert_eq!(game.take_sound(), Some(SoundEvent::Milestone));
+
+        game.game_over();
+        assert_eq!(game.take_sound(), Some(SoundEvent::Collision));
+        assert_eq!(game.take_sound(), Some(SoundEvent::NewHighScore));
+        assert_eq!(game.take_sound(), None);
+    }
+
+    #[test]
+    fn unplayed_sounds_keep_the_latest() {
+        let mut game = playing_game();
+        game.play(SoundEvent::Milestone);
+        for _ in 0..SOUND_QUEUE_SIZE {
+            game.play(SoundEvent::Jump);
+        }
+        game.game_over();
+        assert_eq!(game.take_sound(), Some(SoundEvent::Jump));
+        let mut last = None;
+        while let Some(event) = game.take_sound() {
+            last = Some(event);
+        }
+        assert_eq!(last, Some(SoundEvent::Collision));
+    }
+}
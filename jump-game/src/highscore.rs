@@ -0,0 +1,151 @@
+// Number of scores kept in the table
+pub const HIGH_SCORE_COUNT: usize = 3;
+
+// Size of the table once encoded, see `encode` and `decode`
+pub const ENCODED_SIZE: usize = 4 + HIGH_SCORE_COUNT * 4;
+
+// Marks an initialized table, erased flash reads as 0xFF
+const MAGIC: [u8; 4] = *b"JGHS";
+
+pub type Scores = [u32; HIGH_SCORE_COUNT];
+
+/// Where the high-score table is persisted.
+///
+/// On the board this is a flash partition, on the host `MemoryStorage` can be used.
+pub trait ScoreStorage {
+    type Error;
+
+    /// Loads the table, an empty storage returns all zeros.
+    fn load(&mut self) -> Result<Scores, Self::Error>;
+
+    fn save(&mut self, scores: &Scores) -> Result<(), Self::Error>;
+}
+
+/// Keeps the table in RAM, it is lost on reset.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    scores: Scores,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ScoreStorage for MemoryStorage {
+    type Error = core::convert::Infallible;
+
+    fn load(&mut self) -> Result<Scores, Self::Error> {
+        Ok(self.scores)
+    }
+
+    fn save(&mut self, scores: &Scores) -> Result<(), Self::Error> {
+        self.scores = *scores;
+        Ok(())
+    }
+}
+
+/// Encodes the table as the magic followed by the scores in little endian.
+pub fn encode(scores: &Scores) -> [u8; ENCODED_SIZE] {
+    let mut bytes = [0; ENCODED_SIZE];
+    bytes[..4].copy_from_slice(&MAGIC);
+    for (chunk, score) in bytes[4..].chunks_exact_mut(4).zip(scores) {
+        chunk.copy_from_slice(&score.to_le_bytes());
+    }
+    bytes
+}
+
+/// Decodes a table written by `encode`. Returns all zeros if the magic doesn't match,
+/// which is the case for erased flash.
+pub fn decode(bytes: &[u8; ENCODED_SIZE]) -> Scores {
+    let mut scores = [0; HIGH_SCORE_COUNT];
+    if bytes[..4] != MAGIC {
+        return scores;
+    }
+    for (score, chunk) in scores.iter_mut().zip(bytes[4..].chunks_exact(4)) {
+        *score = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    scores
+}
+
+/// The high-score table, sorted from best to worst.
+pub struct HighScores<S: ScoreStorage> {
+    storage: S,
+    scores: Scores,
+}
+
+impl<S: ScoreStorage> HighScores<S> {
+    /// Loads the table from the storage, starting empty if it can't be read.
+    pub fn new(mut storage: S) -> Self {
+        let mut scores = storage.load().unwrap_or([0; HIGH_SCORE_COUNT]);
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        Self { storage, scores }
+    }
+
+    pub fn scores(&self) -> &Scores {
+        &self.scores
+    }
+
+    pub fn best(&self) -> u32 {
+        self.scores[0]
+    }
+
+    /// Inserts the score if it makes it into the table.
+    ///
+    /// Returns the rank of the new entry, `Some(0)` being a new record.
+    /// The table is only persisted by `save`.
+    pub fn insert(&mut self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self.scores.iter().position(|&s| score > s)?;
+
+        self.scores
+            .copy_within(rank..HIGH_SCORE_COUNT - 1, rank + 1);
+        self.scores[rank] = score;
+        Some(rank)
+    }
+
+    pub fn save(&mut self) -> Result<(), S::Error> {
+        self.storage.save(&self.scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_are_inserted_in_order() {
+        let mut high_scores = HighScores::new(MemoryStorage::new());
+        assert_eq!(high_scores.insert(5), Some(0));
+        assert_eq!(high_scores.insert(9), Some(0));
+        assert_eq!(high_scores.insert(7), Some(1));
+        assert_eq!(high_scores.insert(1), None);
+        assert_eq!(high_scores.insert(0), None);
+        assert_eq!(high_scores.scores(), &[9, 7, 5]);
+    }
+
+    #[test]
+    fn saved_table_is_loaded_back() {
+        let mut high_scores = HighScores::new(MemoryStorage::new());
+        high_scores.insert(3);
+        high_scores.insert(8);
+        high_scores.save().unwrap();
+
+        let high_scores = HighScores::new(high_scores.storage);
+        assert_eq!(high_scores.scores(), &[8, 3, 0]);
+    }
+
+    #[test]
+    fn encoded_table_round_trips() {
+        let scores = [1234, 56, 7];
+        assert_eq!(decode(&encode(&scores)), scores);
+    }
+
+    #[test]
+    fn erased_flash_is_an_empty_table() {
+        assert_eq!(decode(&[0xFF; ENCODED_SIZE]), [0; HIGH_SCORE_COUNT]);
+    }
+}
@@ -0,0 +1,113 @@
+// How long the button must be held before a long press is reported
+pub const LONG_PRESS_MS: u64 = 800;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// The button has just been pressed.
+    Press,
+    /// The button has been held down for at least `LONG_PRESS_MS`.
+    LongPress,
+    /// The button has been released, ends a jump early.
+    Release,
+    /// The duck button has been pressed.
+    DuckStart,
+    /// The duck button has been released.
+    DuckEnd,
+}
+
+/// Turns the raw button level into `InputEvent`s.
+///
+/// A `Press` is reported as soon as the button goes down, so jumping stays
+/// responsive. If the button is still held after `LONG_PRESS_MS`, a single
+/// `LongPress` follows. A `Release` is reported when the button goes up, the time
+/// between the two sets the height of a jump.
+#[derive(Debug, Default)]
+pub struct ButtonInput {
+    pressed_at: Option<u64>,
+    long_press_sent: bool,
+}
+
+impl ButtonInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the current button level, `now_ms` being a monotonic timestamp.
+    pub fn update(&mut self, is_pressed: bool, now_ms: u64) -> Option<InputEvent> {
+        match (is_pressed, self.pressed_at) {
+            (true, None) => {
+                self.pressed_at = Some(now_ms);
+                self.long_press_sent = false;
+                Some(InputEvent::Press)
+            }
+            (true, Some(start)) => {
+                if !self.long_press_sent && now_ms.saturating_sub(start) >= LONG_PRESS_MS {
+                    self.long_press_sent = true;
+                    return Some(InputEvent::LongPress);
+                }
+                None
+            }
+            (false, Some(_)) => {
+                self.pressed_at = None;
+                Some(InputEvent::Release)
+            }
+            (false, None) => None,
+        }
+    }
+}
+
+/// Turns the raw level of the duck button into `DuckStart` and `DuckEnd` events.
+#[derive(Debug, Default)]
+pub struct DuckInput {
+    pressed: bool,
+}
+
+impl DuckInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, is_pressed: bool) -> Option<InputEvent> {
+        if is_pressed == self.pressed {
+            return None;
+        }
+        self.pressed = is_pressed;
+        if is_pressed {
+            Some(InputEvent::DuckStart)
+        } else {
+            Some(InputEvent::DuckEnd)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_reports_press_hold_and_release() {
+        let mut button = ButtonInput::new();
+        assert_eq!(button.update(false, 0), None);
+        assert_eq!(button.update(true, 10), Some(InputEvent::Press));
+        assert_eq!(button.update(true, 200), None);
+        assert_eq!(button.update(false, 250), Some(InputEvent::Release));
+        assert_eq!(button.update(false, 300), None);
+
+        assert_eq!(button.update(true, 1000), Some(InputEvent::Press));
+        assert_eq!(
+            button.update(true, 1000 + LONG_PRESS_MS),
+            Some(InputEvent::LongPress)
+        );
+        assert_eq!(button.update(true, 5000), None);
+        assert_eq!(button.update(false, 5010), Some(InputEvent::Release));
+    }
+
+    #[test]
+    fn duck_button_reports_edges() {
+        let mut duck = DuckInput::new();
+        assert_eq!(duck.update(false), None);
+        assert_eq!(duck.update(true), Some(InputEvent::DuckStart));
+        assert_eq!(duck.update(true), None);
+        assert_eq!(duck.update(false), Some(InputEvent::DuckEnd));
+    }
+}
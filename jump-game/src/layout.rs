@@ -0,0 +1,121 @@
+//! Placement of the game on the display.
+//!
+//! The sprites and the screens were drawn for a 128x64 display. On other sizes the
+//! ground and the text are moved in proportion to the height. Displays lower than
+//! that get the sprites at half height, and the heights above the ground and the
+//! vertical speeds are halved along with them so that a jump still clears the
+//! obstacles in the same number of frames.
+
+use crate::sprites::GROUND_Y;
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+// Height the positions of the sprites and the texts are given for
+const REFERENCE_HEIGHT: i32 = 64;
+
+// Score on the top right corner, with room for it in FONT_6X10
+const SCORE_BOARD_WIDTH: u32 = 68;
+const SCORE_BOARD_HEIGHT: u32 = 10;
+const SCORE_BOARD_Y: i32 = 5;
+
+// The game over image is in the middle of a 64 pixels high display, lower
+// displays need this much room below it for the restart hint
+const GAME_OVER_Y: i32 = 32;
+const GAME_OVER_BOTTOM_MARGIN: i32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub width: i32,
+    pub height: i32,
+    /// Top of the ground sprite.
+    pub ground_y: i32,
+    /// Heights of the sprites are divided by it, 2 on displays lower than 64
+    /// pixels and 1 otherwise.
+    pub scale: i32,
+    /// Area of the score during a run and on the game over screen.
+    pub score_board: Rectangle,
+    /// Top of the game over image.
+    pub game_over_y: i32,
+}
+
+impl Default for Layout {
+    /// Layout of a 128x64 display, the one the sprites were drawn for.
+    fn default() -> Self {
+        Self::new(Size::new(128, REFERENCE_HEIGHT as u32))
+    }
+}
+
+impl Layout {
+    /// Lays the game out on a display of `size`, usually its `bounding_box().size`.
+    pub fn new(size: Size) -> Self {
+        let (width, height) = (size.width as i32, size.height as i32);
+        let score_board = Rectangle::new(
+            Point::new(
+                width - SCORE_BOARD_WIDTH as i32,
+                SCORE_BOARD_Y * height / REFERENCE_HEIGHT,
+            ),
+            Size::new(SCORE_BOARD_WIDTH, SCORE_BOARD_HEIGHT),
+        );
+        Self {
+            width,
+            height,
+            ground_y: GROUND_Y * height / REFERENCE_HEIGHT,
+            scale: if height < REFERENCE_HEIGHT { 2 } else { 1 },
+            score_board,
+            game_over_y: GAME_OVER_Y.min(height - GAME_OVER_BOTTOM_MARGIN),
+        }
+    }
+
+    /// Moves a Y position of the sprite constants along with the ground.
+    pub fn sprite_y(&self, y: i32) -> i32 {
+        self.ground_y - (GROUND_Y - y) / self.scale
+    }
+
+    /// Scales a vertical speed in pixels per frame, rounded up so that the
+    /// T-Rex never spends longer in the air than on a 64 pixels high display.
+    pub fn scale_speed(&self, speed: i32) -> i32 {
+        (speed + self.scale - 1) / self.scale
+    }
+
+    /// Moves a line of text placed for a 64 pixels high display in proportion.
+    pub fn text_y(&self, y: i32) -> i32 {
+        y * self.height / REFERENCE_HEIGHT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sprites::{TREX_GROUND_Y, TREX_MIN_Y};
+
+    #[test]
+    fn reference_display_keeps_the_sprite_positions() {
+        let layout = Layout::default();
+        assert_eq!(layout.ground_y, GROUND_Y);
+        assert_eq!(layout.scale, 1);
+        assert_eq!(layout.scale_speed(7), 7);
+        assert_eq!(layout.sprite_y(TREX_GROUND_Y), TREX_GROUND_Y);
+        assert_eq!(layout.text_y(20), 20);
+        assert_eq!(
+            layout.score_board,
+            Rectangle::new(Point::new(60, 5), Size::new(68, 10))
+        );
+        assert_eq!(layout.game_over_y, 32);
+    }
+
+    #[test]
+    fn short_display_scales_the_ground_and_the_jump() {
+        let layout = Layout::new(Size::new(128, 32));
+        assert_eq!(layout.ground_y, 27);
+        assert_eq!(layout.scale, 2);
+        assert_eq!(
+            layout.sprite_y(TREX_GROUND_Y) - layout.sprite_y(TREX_MIN_Y),
+            (TREX_GROUND_Y - TREX_MIN_Y) / 2
+        );
+        // The whole jump stays on the screen
+        assert_eq!(layout.sprite_y(TREX_GROUND_Y), 15);
+        assert!(layout.sprite_y(TREX_MIN_Y) >= 0);
+        assert_eq!(layout.scale_speed(7), 4);
+        assert_eq!(layout.score_board.top_left, Point::new(60, 2));
+        assert_eq!(layout.game_over_y, 12);
+    }
+}
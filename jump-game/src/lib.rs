@@ -1,4 +1,14 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+pub mod autopilot;
+pub mod difficulty;
 pub mod game;
+pub mod highscore;
+pub mod input;
+pub mod layout;
+pub mod replay;
 pub mod rng;
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+pub mod sound;
 pub mod sprites;
+pub mod timestep;
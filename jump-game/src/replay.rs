@@ -0,0 +1,382 @@
+//! Input recording and deterministic replay.
+//!
+//! A run is fully determined by the state of the `SeededRng` when it starts and by
+//! the updates on which the input events happen, so that is all a `Recording` keeps.
+//!
+//! Events are stored as a varint of `(ticks since the previous event << 3) | event`,
+//! events up to 15 updates apart take a single byte.
+
+use crate::game::{Game, GameState};
+use crate::highscore::ScoreStorage;
+use crate::input::InputEvent;
+use crate::rng::SeededRng;
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::DrawTarget};
+use heapless::Vec;
+
+// Size of the event log, in bytes
+pub const LOG_SIZE: usize = 512;
+
+// Low bits of a log entry holding the event
+const EVENT_BITS: u32 = 3;
+
+/// Seed and input events of one run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    seed: u32,
+    log: Vec<u8, LOG_SIZE>,
+    last_tick: u32,
+    truncated: bool,
+}
+
+impl Recording {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            log: Vec::new(),
+            last_tick: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// The encoded events.
+    pub fn log(&self) -> &[u8] {
+        &self.log
+    }
+
+    /// Set when the log was full and events were dropped, the replay won't match the run.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Appends an event that happened before the update `tick` of the run.
+    pub fn push(&mut self, tick: u32, event: InputEvent) {
+        if self.truncated {
+            return;
+        }
+        let entry = (tick.saturating_sub(self.last_tick) << EVENT_BITS) | encode_event(event);
+        let mut bytes = [0; 5];
+        let len = write_varint(entry, &mut bytes);
+        if self.log.extend_from_slice(&bytes[..len]).is_err() {
+            self.truncated = true;
+            return;
+        }
+        self.last_tick = tick;
+    }
+
+    /// Iterates over the events with the tick they happened on.
+    pub fn events(&self) -> Events<'_> {
+        Events {
+            log: &self.log,
+            tick: 0,
+        }
+    }
+
+    /// Loads a recording stored as the seed in little endian followed by the log,
+    /// the format used for the regression fixtures.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (seed, log) = bytes.split_first_chunk::<4>()?;
+        let mut recording = Self::new(u32::from_le_bytes(*seed));
+        recording.log = Vec::from_slice(log).ok()?;
+        recording.last_tick = recording.events().last().map_or(0, |(tick, _)| tick);
+        Some(recording)
+    }
+}
+
+pub struct Events<'a> {
+    log: &'a [u8],
+    tick: u32,
+}
+
+impl Iterator for Events<'_> {
+    type Item = (u32, InputEvent);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (entry, len) = read_varint(self.log)?;
+        self.log = &self.log[len..];
+        self.tick += entry >> EVENT_BITS;
+        Some((self.tick, decode_event(entry)?))
+    }
+}
+
+/// Records the input of the runs of a game.
+///
+/// Input goes through `handle_input` instead of `Game::handle_input`; each run
+/// that starts replaces the previous recording.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    recording: Option<Recording>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current or last recorded run.
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    pub fn handle_input<D, S>(&mut self, game: &mut Game<D, SeededRng, S>, event: InputEvent)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        S: ScoreStorage,
+    {
+        let seed = game.obstacles.rng.state();
+        let was_running = matches!(game.state, GameState::Playing | GameState::Paused);
+
+        game.handle_input(event);
+
+        if !was_running && game.state == GameState::Playing {
+            self.recording = Some(Recording::new(seed));
+        } else if let (true, Some(recording)) = (was_running, self.recording.as_mut()) {
+            recording.push(game.run_ticks(), event);
+        }
+    }
+}
+
+/// Plays a recording back into a game.
+///
+/// Call `feed` before each `Game::step` until the game is over; live input must
+/// not reach the game meanwhile.
+#[derive(Debug)]
+pub struct Replay {
+    // Bytes of the log already played
+    position: usize,
+    tick: u32,
+}
+
+impl Replay {
+    /// Restarts the game from the seed of the recording.
+    pub fn start<D, S>(game: &mut Game<D, SeededRng, S>, recording: &Recording) -> Self
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        S: ScoreStorage,
+    {
+        game.obstacles.rng = SeededRng::new(recording.seed);
+        game.set_high_scores_enabled(false);
+        game.restart();
+        Self {
+            position: 0,
+            tick: 0,
+        }
+    }
+
+    /// Applies the events due before the next update.
+    pub fn feed<D, S>(&mut self, game: &mut Game<D, SeededRng, S>, recording: &Recording)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        S: ScoreStorage,
+    {
+        while let Some((entry, len)) = read_varint(&recording.log[self.position..]) {
+            let tick = self.tick + (entry >> EVENT_BITS);
+            if tick > game.run_ticks() {
+                break;
+            }
+            self.position += len;
+            self.tick = tick;
+            if let Some(event) = decode_event(entry) {
+                game.handle_input(event);
+            }
+        }
+    }
+
+    /// Gives the game back to the player.
+    pub fn finish<D, S>(self, game: &mut Game<D, SeededRng, S>)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        S: ScoreStorage,
+    {
+        game.set_high_scores_enabled(true);
+    }
+}
+
+fn encode_event(event: InputEvent) -> u32 {
+    match event {
+        InputEvent::Press => 0,
+        InputEvent::LongPress => 1,
+        InputEvent::DuckStart => 2,
+        InputEvent::DuckEnd => 3,
+        InputEvent::Release => 4,
+    }
+}
+
+fn decode_event(entry: u32) -> Option<InputEvent> {
+    match entry & ((1 << EVENT_BITS) - 1) {
+        0 => Some(InputEvent::Press),
+        1 => Some(InputEvent::LongPress),
+        2 => Some(InputEvent::DuckStart),
+        3 => Some(InputEvent::DuckEnd),
+        4 => Some(InputEvent::Release),
+        _ => None,
+    }
+}
+
+fn write_varint(mut value: u32, out: &mut [u8; 5]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out[len] = byte;
+            return len + 1;
+        }
+        out[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+fn read_varint(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+    for (idx, byte) in bytes.iter().enumerate().take(5) {
+        value |= u32::from(byte & 0x7F) << (7 * idx);
+        if byte & 0x80 == 0 {
+            return Some((value, idx + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::DifficultyConfig;
+    use crate::sim::{self, SimGame};
+    use crate::sprites::{PTERO_HIGH_Y, TREX_X};
+    use embedded_graphics::geometry::Dimensions;
+
+    // Run recorded with `play` on seed 5, it scored 72 points and ended on the update 733.
+    // Re-record it when the physics change on purpose.
+    const FIXTURE_SEED_5: &[u8] = include_bytes!("../fixtures/replays/seed-5.bin");
+
+    /// Plays a run with a simple rule: jump or duck when the next obstacle gets close.
+    fn play(game: &mut SimGame, recorder: &mut Recorder) {
+        recorder.handle_input(game, InputEvent::Press);
+        let (mut ducking, mut next_x, mut pressed) = (false, 0, false);
+        while game.state == GameState::Playing {
+            let next = game
+                .obstacles
+                .buffer
+                .iter()
+                .map(|obs| obs.img.bounding_box())
+                .find(|bbox| bbox.top_left.x + bbox.size.width as i32 > TREX_X);
+            if let Some(bbox) = next {
+                // A single press per obstacle, more would be buffered into a second jump
+                pressed &= bbox.top_left.x <= next_x;
+                next_x = bbox.top_left.x;
+                let close = bbox.top_left.x - 35 <= 24;
+                let high = bbox.top_left.y == game.layout().sprite_y(PTERO_HIGH_Y);
+                if high && close && !ducking {
+                    recorder.handle_input(game, InputEvent::DuckStart);
+                    ducking = true;
+                } else if !high && ducking {
+                    recorder.handle_input(game, InputEvent::DuckEnd);
+                    ducking = false;
+                }
+                if !high && close && !pressed {
+                    recorder.handle_input(game, InputEvent::Press);
+                    pressed = true;
+                }
+            }
+            game.update().unwrap();
+        }
+    }
+
+    fn replay(game: &mut SimGame, recording: &Recording) {
+        let mut replay = Replay::start(game, recording);
+        while game.state == GameState::Playing {
+            replay.feed(game, recording);
+            game.update().unwrap();
+        }
+        replay.finish(game);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, 16_383, 16_384, u32::MAX] {
+            let mut bytes = [0; 5];
+            let len = write_varint(value, &mut bytes);
+            assert_eq!(read_varint(&bytes[..len]), Some((value, len)));
+        }
+        assert_eq!(read_varint(&[0x80, 0x80]), None);
+    }
+
+    #[test]
+    fn events_keep_their_ticks() {
+        let mut recording = Recording::new(1);
+        recording.push(3, InputEvent::Press);
+        recording.push(3, InputEvent::LongPress);
+        recording.push(500, InputEvent::DuckStart);
+        recording.push(501, InputEvent::DuckEnd);
+        recording.push(510, InputEvent::Release);
+
+        let events: std::vec::Vec<_> = recording.events().collect();
+        assert_eq!(
+            events,
+            [
+                (3, InputEvent::Press),
+                (3, InputEvent::LongPress),
+                (500, InputEvent::DuckStart),
+                (501, InputEvent::DuckEnd),
+                (510, InputEvent::Release),
+            ]
+        );
+        // Short delays take a single byte
+        assert_eq!(recording.log().len(), 1 + 1 + 2 + 1 + 1);
+    }
+
+    #[test]
+    fn full_log_is_truncated() {
+        let mut recording = Recording::new(1);
+        for tick in 0..LOG_SIZE as u32 + 1 {
+            recording.push(tick, InputEvent::Press);
+        }
+        assert!(recording.is_truncated());
+        assert_eq!(recording.log().len(), LOG_SIZE);
+    }
+
+    #[test]
+    fn recorded_run_replays_exactly() {
+        let mut game = sim::new_game(11, DifficultyConfig::default());
+        let mut recorder = Recorder::new();
+        play(&mut game, &mut recorder);
+        let recording = recorder.recording().unwrap().clone();
+        assert!(game.score() > 0);
+
+        // Replay into a game that has already played, from a different seed
+        let mut replayed = sim::new_game(99, DifficultyConfig::default());
+        play(&mut replayed, &mut Recorder::new());
+        replay(&mut replayed, &recording);
+
+        assert_eq!(replayed.score(), game.score());
+        assert_eq!(replayed.run_ticks(), game.run_ticks());
+        assert_eq!(replayed.new_high_score(), None);
+    }
+
+    #[test]
+    fn fixture_replays_to_the_same_score() {
+        let recording = Recording::from_bytes(FIXTURE_SEED_5).unwrap();
+        let mut game = sim::new_game(1, DifficultyConfig::default());
+        replay(&mut game, &recording);
+
+        assert_eq!(game.score(), 72);
+        assert_eq!(game.run_ticks(), 733);
+    }
+
+    #[test]
+    fn stored_recording_loads_back() {
+        let mut game = sim::new_game(5, DifficultyConfig::default());
+        let mut recorder = Recorder::new();
+        play(&mut game, &mut recorder);
+        let recording = recorder.recording().unwrap();
+
+        let mut bytes = std::vec::Vec::from(recording.seed().to_le_bytes());
+        bytes.extend_from_slice(recording.log());
+        assert_eq!(Recording::from_bytes(&bytes).as_ref(), Some(recording));
+        assert_eq!(bytes, FIXTURE_SEED_5);
+    }
+}
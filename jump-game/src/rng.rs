@@ -1,3 +1,35 @@
 pub trait Rng {
     fn random_u32(&mut self) -> u32;
 }
+
+/// Xorshift generator, the same seed always gives the same numbers.
+///
+/// The game runs on it so that a run can be replayed from its seed, the hardware
+/// generator only provides the seed.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u32,
+}
+
+impl SeededRng {
+    pub fn new(seed: u32) -> Self {
+        // Xorshift gets stuck on zero
+        Self { state: seed.max(1) }
+    }
+
+    /// Current state, a generator created with it as seed gives the same numbers from here.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+}
+
+impl Rng for SeededRng {
+    fn random_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+}
@@ -0,0 +1,79 @@
+//! Helpers to run the game off-target, built for the tests and with the `sim`
+//! feature only, the firmware doesn't need them.
+//!
+//! The unit tests drive `Game` with these on the host:
+//!
+//! ```text
+//! cargo +stable test --lib --target x86_64-unknown-linux-gnu
+//! ```
+
+use crate::difficulty::DifficultyConfig;
+use crate::game::Game;
+use crate::highscore::MemoryStorage;
+use crate::rng::SeededRng;
+use oled_dirty::PageBuffer;
+
+pub const WIDTH: usize = 128;
+pub const HEIGHT: usize = 64;
+
+pub type SimGame = Game<FrameBuffer, SeededRng, MemoryStorage>;
+
+/// Builds a game drawing into a `FrameBuffer` with a seeded rng.
+pub fn new_game(seed: u32, difficulty: DifficultyConfig) -> SimGame {
+    Game::new(
+        SeededRng::new(seed),
+        FrameBuffer::new(),
+        MemoryStorage::new(),
+        difficulty,
+    )
+}
+
+/// In-memory 128x64 display, in the same layout as the SSD1306 memory.
+pub type FrameBuffer = PageBuffer<WIDTH, { HEIGHT / 8 }>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::input::InputEvent;
+
+    fn start(seed: u32) -> SimGame {
+        let mut game = new_game(seed, DifficultyConfig::default());
+        game.handle_input(InputEvent::Press);
+        game
+    }
+
+    #[test]
+    fn main_menu_is_drawn_once() {
+        let mut game = new_game(1, DifficultyConfig::default());
+        assert!(game.update().unwrap());
+        assert!(game.display.count_on() > 0);
+        assert!(!game.update().unwrap());
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_run() {
+        let mut a = start(42);
+        let mut b = start(42);
+        for _ in 0..500 {
+            a.update().unwrap();
+            b.update().unwrap();
+            assert_eq!(a.state, b.state);
+            assert_eq!(a.score(), b.score());
+            assert_eq!(a.display, b.display);
+        }
+    }
+
+    #[test]
+    fn idle_trex_crashes_into_the_first_cactus() {
+        let mut game = start(7);
+        let mut frames = 0;
+        while game.state == GameState::Playing {
+            game.update().unwrap();
+            frames += 1;
+            assert!(frames < 100, "the T-Rex never hit anything");
+        }
+        assert_eq!(game.state, GameState::GameOver);
+        assert_eq!(game.score(), 0);
+    }
+}
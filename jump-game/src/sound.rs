@@ -0,0 +1,60 @@
+//! Sound effects.
+//!
+//! `Game` queues a `SoundEvent` for everything the player should hear, the firmware
+//! takes them with `Game::take_sound` and plays the matching `effect` on the buzzer.
+
+// Points between two milestone jingles
+pub const MILESTONE_POINTS: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundEvent {
+    /// The T-Rex jumped.
+    Jump,
+    /// The score reached a multiple of `MILESTONE_POINTS`.
+    Milestone,
+    /// The T-Rex hit an obstacle.
+    Collision,
+    /// The run ended with the best score of the high-score table.
+    NewHighScore,
+}
+
+/// A note of an effect, a frequency of 0 is a silence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    pub freq_hz: u32,
+    pub duration_ms: u32,
+}
+
+const fn tone(freq_hz: u32, duration_ms: u32) -> Tone {
+    Tone {
+        freq_hz,
+        duration_ms,
+    }
+}
+
+// Kept short, the buzzer plays one effect at a time
+const JUMP: &[Tone] = &[tone(660, 30), tone(880, 40)];
+const MILESTONE: &[Tone] = &[
+    tone(1047, 60),
+    tone(0, 20),
+    tone(1319, 60),
+    tone(0, 20),
+    tone(1568, 90),
+];
+const COLLISION: &[Tone] = &[tone(196, 80), tone(147, 160)];
+const NEW_HIGH_SCORE: &[Tone] = &[
+    tone(784, 80),
+    tone(988, 80),
+    tone(1175, 80),
+    tone(1568, 200),
+];
+
+/// Tones played for an event.
+pub fn effect(event: SoundEvent) -> &'static [Tone] {
+    match event {
+        SoundEvent::Jump => JUMP,
+        SoundEvent::Milestone => MILESTONE,
+        SoundEvent::Collision => COLLISION,
+        SoundEvent::NewHighScore => NEW_HIGH_SCORE,
+    }
+}
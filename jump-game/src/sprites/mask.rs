@@ -0,0 +1,82 @@
+use embedded_graphics::{
+    prelude::{Point, PointsIter, Size},
+    primitives::Rectangle,
+};
+
+/// Solid pixels of a 1 bpp sprite, read straight from its `SPRITE_*` bitmap.
+///
+/// The bitmap uses the same layout as `ImageRaw`: rows are padded to a whole byte
+/// and the most significant bit is the leftmost pixel.
+#[derive(Debug)]
+pub struct CollisionMask {
+    data: &'static [u8],
+    width: u32,
+}
+
+impl CollisionMask {
+    pub const fn new(data: &'static [u8], width: u32) -> Self {
+        Self { data, width }
+    }
+
+    const fn bytes_per_row(&self) -> usize {
+        (self.width as usize).div_ceil(8)
+    }
+
+    pub const fn size(&self) -> Size {
+        Size::new(self.width, (self.data.len() / self.bytes_per_row()) as u32)
+    }
+
+    /// Returns `true` if the pixel at `point`, relative to the top left corner, is set.
+    pub fn is_solid(&self, point: Point) -> bool {
+        let size = self.size();
+        if point.x < 0
+            || point.y < 0
+            || point.x >= size.width as i32
+            || point.y >= size.height as i32
+        {
+            return false;
+        }
+        let byte = self.data[point.y as usize * self.bytes_per_row() + point.x as usize / 8];
+        byte & (0x80 >> (point.x % 8)) != 0
+    }
+}
+
+/// Checks whether two sprites drawn at `a_pos` and `b_pos` have a solid pixel in common.
+pub fn masks_overlap(a: &CollisionMask, a_pos: Point, b: &CollisionMask, b_pos: Point) -> bool {
+    let area = Rectangle::new(a_pos, a.size()).intersection(&Rectangle::new(b_pos, b.size()));
+    area.points()
+        .any(|point| a.is_solid(point - a_pos) && b.is_solid(point - b_pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10x2 sprite, only the first and the last pixel of the first row are set
+    const CORNERS: CollisionMask = CollisionMask::new(&[0x80, 0x40, 0x00, 0x00], 10);
+    const SOLID: CollisionMask = CollisionMask::new(&[0xFF; 4], 8);
+
+    #[test]
+    fn rows_are_padded_to_whole_bytes() {
+        assert_eq!(CORNERS.size(), Size::new(10, 2));
+        assert!(CORNERS.is_solid(Point::new(0, 0)));
+        assert!(CORNERS.is_solid(Point::new(9, 0)));
+        assert!(!CORNERS.is_solid(Point::new(1, 0)));
+        assert!(!CORNERS.is_solid(Point::new(0, 1)));
+    }
+
+    #[test]
+    fn pixels_outside_the_sprite_are_empty() {
+        assert!(!SOLID.is_solid(Point::new(-1, 0)));
+        assert!(!SOLID.is_solid(Point::new(8, 0)));
+        assert!(!SOLID.is_solid(Point::new(0, 4)));
+    }
+
+    #[test]
+    fn only_solid_pixels_overlap() {
+        let origin = Point::zero();
+        assert!(masks_overlap(&CORNERS, origin, &SOLID, Point::new(9, -3)));
+        assert!(!masks_overlap(&CORNERS, origin, &SOLID, Point::new(1, 1)));
+        assert!(!masks_overlap(&CORNERS, origin, &SOLID, Point::new(20, 0)));
+    }
+}
@@ -1,32 +1,164 @@
+mod mask;
 mod resources;
 
+pub use oled_animation::Animation;
+pub use mask::{masks_overlap, CollisionMask};
 use resources::*;
+pub use resources::{RAW_GAME_OVER, RAW_TREX};
 
+use crate::difficulty::DifficultyConfig;
+use crate::layout::Layout;
 use embedded_graphics::{
+    geometry::Dimensions,
     image::{Image, ImageRaw},
     pixelcolor::BinaryColor,
     prelude::{Point, Transform},
+    primitives::Rectangle,
 };
 use heapless::spsc::Queue;
-const BUFF_SIZE: usize = 4;
-const OLED_WIDTH: i32 = 128;
-// const OLED_HEIGHT: i32 = 64;
+pub const BUFF_SIZE: usize = 4;
 
 type ImgRawType = ImageRaw<'static, BinaryColor>;
 
-// Raw image of sprites
-const RAW_GROUND: ImgRawType = ImageRaw::new(&SPRITE_GROUND, GROUND_X_LENGTH as u32);
-const RAW_TREX: ImgRawType = ImageRaw::new(&SPRITE_TREX, 25);
-const RAW_CACTUS1: ImgRawType = ImageRaw::new(&SPRITE_CACTUS1, 11);
-const RAW_CACTUS2: ImgRawType = ImageRaw::new(&SPRITE_CACTUS2, 22);
-const RAW_CACTUS3: ImgRawType = ImageRaw::new(&SPRITE_CACTUS3, 21);
+// A frame of the T-Rex, with the mask of its bitmap
+type Frame = (ImgRawType, CollisionMask);
 
-pub const RAW_GAME_OVER: ImgRawType = ImageRaw::new(&SPRITE_GAME_OVER, 100);
+// Images of the T-Rex and of the obstacles with their collision masks, read from the
+// same bitmaps. The displays lower than 64 pixels get them at half height.
+#[derive(Debug)]
+struct SpriteSet {
+    trex_air: Frame,
+    // The legs move while the T-Rex runs or ducks, they stay still in the air
+    trex_run: [Frame; TREX_RUN_FRAMES],
+    trex_duck: [Frame; TREX_DUCK_FRAMES],
+    // Spawn pool of obstacles with their Y position, the first two start a run
+    obstacles: [(ImgRawType, CollisionMask, i32); 5],
+}
 
-const OBSTACLES: [ImgRawType; 3] = [RAW_CACTUS1, RAW_CACTUS2, RAW_CACTUS3];
+static FULL_HEIGHT: SpriteSet = SpriteSet {
+    trex_air: (RAW_TREX, CollisionMask::new(&SPRITE_TREX, TREX_WIDTH)),
+    trex_run: [
+        (
+            RAW_TREX_RUN[0],
+            CollisionMask::new(&SPRITE_TREX_RUN[0], TREX_RUN_WIDTH),
+        ),
+        (
+            RAW_TREX_RUN[1],
+            CollisionMask::new(&SPRITE_TREX_RUN[1], TREX_RUN_WIDTH),
+        ),
+    ],
+    trex_duck: [
+        (
+            RAW_TREX_DUCK[0],
+            CollisionMask::new(&SPRITE_TREX_DUCK[0], TREX_DUCK_WIDTH),
+        ),
+        (
+            RAW_TREX_DUCK[1],
+            CollisionMask::new(&SPRITE_TREX_DUCK[1], TREX_DUCK_WIDTH),
+        ),
+    ],
+    obstacles: [
+        (
+            RAW_CACTUS1,
+            CollisionMask::new(&SPRITE_CACTUS1, CACTUS1_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_CACTUS2,
+            CollisionMask::new(&SPRITE_CACTUS2, CACTUS2_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_CACTUS3,
+            CollisionMask::new(&SPRITE_CACTUS3, CACTUS3_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_PTERO,
+            CollisionMask::new(&SPRITE_PTERO, PTERO_WIDTH),
+            PTERO_LOW_Y,
+        ),
+        (
+            RAW_PTERO,
+            CollisionMask::new(&SPRITE_PTERO, PTERO_WIDTH),
+            PTERO_HIGH_Y,
+        ),
+    ],
+};
+
+static HALF_HEIGHT: SpriteSet = SpriteSet {
+    trex_air: (
+        RAW_TREX_HALF,
+        CollisionMask::new(&SPRITE_TREX_HALF, TREX_WIDTH),
+    ),
+    trex_run: [
+        (
+            RAW_TREX_RUN_HALF[0],
+            CollisionMask::new(&SPRITE_TREX_RUN_HALF[0], TREX_RUN_WIDTH),
+        ),
+        (
+            RAW_TREX_RUN_HALF[1],
+            CollisionMask::new(&SPRITE_TREX_RUN_HALF[1], TREX_RUN_WIDTH),
+        ),
+    ],
+    trex_duck: [
+        (
+            RAW_TREX_DUCK_HALF[0],
+            CollisionMask::new(&SPRITE_TREX_DUCK_HALF[0], TREX_DUCK_WIDTH),
+        ),
+        (
+            RAW_TREX_DUCK_HALF[1],
+            CollisionMask::new(&SPRITE_TREX_DUCK_HALF[1], TREX_DUCK_WIDTH),
+        ),
+    ],
+    obstacles: [
+        (
+            RAW_CACTUS1_HALF,
+            CollisionMask::new(&SPRITE_CACTUS1_HALF, CACTUS1_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_CACTUS2_HALF,
+            CollisionMask::new(&SPRITE_CACTUS2_HALF, CACTUS2_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_CACTUS3_HALF,
+            CollisionMask::new(&SPRITE_CACTUS3_HALF, CACTUS3_WIDTH),
+            CACTUS_Y,
+        ),
+        (
+            RAW_PTERO_HALF,
+            CollisionMask::new(&SPRITE_PTERO_HALF, PTERO_WIDTH),
+            PTERO_LOW_Y,
+        ),
+        (
+            RAW_PTERO_HALF,
+            CollisionMask::new(&SPRITE_PTERO_HALF, PTERO_WIDTH),
+            PTERO_HIGH_Y,
+        ),
+    ],
+};
+
+impl SpriteSet {
+    // Images for the heights divided by `scale`, see `Layout::scale`
+    fn for_scale(scale: i32) -> &'static SpriteSet {
+        match scale {
+            1 => &FULL_HEIGHT,
+            _ => &HALF_HEIGHT,
+        }
+    }
+}
+
+// Updates each frame of the legs is shown
+const TREX_RUN_FRAME_TICKS: u32 = 2;
+const TREX_DUCK_FRAME_TICKS: u32 = 3;
+
+// Positions below are the ones on a 128x64 display, `Layout::sprite_y` moves them
+// along with the ground on other sizes and scales their height above it
 
 // Ground line Info
-pub const GROUND_X_LENGTH: i32 = 1200;
+pub const GROUND_X_LENGTH: i32 = GROUND_WIDTH as i32;
 pub const GROUND_Y: i32 = 54;
 pub const GROUND_X_START: i32 = 0;
 // pub const GROUND_X_END: i32 = 127;
@@ -36,21 +168,31 @@ pub const TREX_X: i32 = 10;
 // pub const TREX_INIT_Y: i32 = 29;
 pub const TREX_GROUND_Y: i32 = 29;
 pub const TREX_MIN_Y: i32 = 3;
+// A jump released early turns into a hop, which still goes up to this height
+pub const TREX_HOP_Y: i32 = 17;
+// Ducking sprite is shorter, its feet stay on the same line as the running one
+pub const TREX_DUCK_Y: i32 = 40;
 
 // Cactus info
 pub const CACTUS_Y: i32 = 35;
 
-// Movements - Adjust these values to redue/increase speed
+// Widest sprite in the spawn pool
+pub const OBSTACLE_MAX_WIDTH: i32 = 24;
+
+// Pterodactyl info
+// Low one has to be jumped over, the high one can only be avoided by ducking
+pub const PTERO_LOW_Y: i32 = 36;
+pub const PTERO_HIGH_Y: i32 = 24;
+
+// Movements - Speed of the obstacles and gravity are set through `DifficultyConfig`
 pub const TREX_VELOCITY: i32 = -7;
-pub const GRAVITY: i32 = 7;
-pub const OBSTACLE_VELOCITY: i32 = -12; // obstacles moving left side, so it is X-velocity
-pub const OBSTACLE_GAP: i32 = 100;
 
 #[derive(Debug, PartialEq)]
 pub enum TrexState {
     Running,
     Jumping,
     Falling,
+    Ducking,
 }
 
 #[derive(Debug)]
@@ -58,46 +200,153 @@ pub struct Trex {
     pub img: Image<'static, ImgRawType>,
     pub position: Point,
     pub state: TrexState,
+    gravity: i32,
+    // Speed of the way up, negative
+    velocity: i32,
+    // Heights of the jump are divided by it, see `Layout::scale`
+    scale: i32,
+    sprites: &'static SpriteSet,
+    // Cleared when the button is released, the jump stops going up
+    jump_held: bool,
+    // Y position when running, the heights of the jump are relative to it
+    ground_y: i32,
+    // Only the animation of the current state moves
+    running: Animation<'static, Frame>,
+    ducking: Animation<'static, Frame>,
 }
 
 impl Trex {
-    pub fn new(x: i32, y: i32) -> Self {
+    /// Creates a T-Rex running at `y`, `TREX_GROUND_Y` on a 128x64 display.
+    pub fn new(x: i32, y: i32, gravity: i32) -> Self {
+        Self::scaled(x, y, gravity, 1)
+    }
+
+    /// Creates the T-Rex of a run on the display of `layout`, falling at
+    /// `gravity` on a 128x64 display.
+    pub fn for_layout(layout: &Layout, gravity: i32) -> Self {
+        Self::scaled(
+            TREX_X,
+            layout.sprite_y(TREX_GROUND_Y),
+            layout.scale_speed(gravity),
+            layout.scale,
+        )
+    }
+
+    fn scaled(x: i32, y: i32, gravity: i32, scale: i32) -> Self {
+        let sprites = SpriteSet::for_scale(scale);
         let position = Point::new(x, y);
-        let image = Image::new(&RAW_TREX, position);
+        let running = Animation::new(&sprites.trex_run, TREX_RUN_FRAME_TICKS);
+        let image = Image::new(&running.frame().0, position);
         Self {
             img: image,
             state: TrexState::Running,
             position,
+            gravity,
+            velocity: -(-TREX_VELOCITY + scale - 1) / scale,
+            scale,
+            sprites,
+            jump_held: true,
+            ground_y: y,
+            running,
+            ducking: Animation::new(&sprites.trex_duck, TREX_DUCK_FRAME_TICKS),
         }
     }
 
+    // Frame of the current state
+    fn frame(&self) -> &'static Frame {
+        match self.state {
+            TrexState::Running => self.running.frame(),
+            TrexState::Ducking => self.ducking.frame(),
+            TrexState::Jumping | TrexState::Falling => &self.sprites.trex_air,
+        }
+    }
+
+    // Moves a position of the T-Rex constants to where the T-Rex runs
+    fn relative_y(&self, y: i32) -> i32 {
+        self.ground_y - (TREX_GROUND_Y - y) / self.scale
+    }
+
     pub fn update_posistion(&mut self, x: i32, y: i32) {
         //TODO:: updating existing image
-        self.img = Image::new(&RAW_TREX, Point::new(x, y));
+        let y = match self.state {
+            TrexState::Ducking => self.relative_y(TREX_DUCK_Y),
+            _ => y,
+        };
+        self.img = Image::new(&self.frame().0, Point::new(x, y));
         // self.img = self.img.translate(Point::new(self.position.x, velocity));
     }
 
+    /// Area used for the collision checks, it follows the sprite of the current state.
+    pub fn hitbox(&self) -> Rectangle {
+        self.img.bounding_box()
+    }
+
+    /// Collision mask of the sprite of the current state.
+    pub fn mask(&self) -> &'static CollisionMask {
+        &self.frame().1
+    }
+
+    /// Starts or stops ducking. Ducking in the air makes the T-Rex fall right away.
+    pub fn duck(&mut self, ducking: bool) {
+        self.state = match (&self.state, ducking) {
+            (TrexState::Running, true) => TrexState::Ducking,
+            (TrexState::Jumping, true) => TrexState::Falling,
+            (TrexState::Ducking, false) => TrexState::Running,
+            _ => return,
+        };
+        self.update_posistion(self.position.x, self.position.y);
+    }
+
+    /// Starts a jump from the ground, it goes up to `TREX_MIN_Y` unless `release_jump`
+    /// is called on the way up.
+    pub fn jump(&mut self) -> bool {
+        if self.state != TrexState::Running {
+            return false;
+        }
+        self.state = TrexState::Jumping;
+        self.jump_held = true;
+        self.update_state();
+        true
+    }
+
+    /// Ends the way up of the jump, once it is higher than `TREX_HOP_Y`.
+    pub fn release_jump(&mut self) {
+        self.jump_held = false;
+    }
+
     pub fn update_state(&mut self) {
         match self.state {
             TrexState::Jumping => {
                 // Velocity is negative, the Y value decreases, causing the T-Rex to move upwards
-                self.position.y += TREX_VELOCITY;
-                if self.position.y <= TREX_MIN_Y {
-                    self.position.y = TREX_MIN_Y;
+                self.position.y += self.velocity;
+                let min_y = self.relative_y(TREX_MIN_Y);
+                if self.position.y <= min_y {
+                    self.position.y = min_y;
+                    self.state = TrexState::Falling;
+                } else if !self.jump_held && self.position.y <= self.relative_y(TREX_HOP_Y) {
                     self.state = TrexState::Falling;
                 }
                 self.update_posistion(self.position.x, self.position.y);
             }
             TrexState::Falling => {
                 //Gravity is positive, the Y value increase, causing the T-Rex to move downwards
-                self.position.y += GRAVITY;
-                if self.position.y >= TREX_GROUND_Y {
-                    self.position.y = TREX_GROUND_Y;
+                self.position.y += self.gravity;
+                if self.position.y >= self.ground_y {
+                    self.position.y = self.ground_y;
                     self.state = TrexState::Running;
                 }
                 self.update_posistion(self.position.x, self.position.y);
             }
-            _ => (),
+            TrexState::Running => {
+                if self.running.tick() {
+                    self.update_posistion(self.position.x, self.position.y);
+                }
+            }
+            TrexState::Ducking => {
+                if self.ducking.tick() {
+                    self.update_posistion(self.position.x, self.position.y);
+                }
+            }
         };
     }
 }
@@ -105,14 +354,15 @@ impl Trex {
 #[derive(Debug)]
 pub struct Obstacle {
     pub img: Image<'static, ImgRawType>,
+    pub mask: &'static CollisionMask,
     pub x: i32,
     // pub y: i32,
 }
 
 impl Obstacle {
-    pub fn new(raw_img: &'static ImgRawType, x: i32, y: i32) -> Self {
+    pub fn new(raw_img: &'static ImgRawType, mask: &'static CollisionMask, x: i32, y: i32) -> Self {
         let img = Image::new(raw_img, Point::new(x, y));
-        Self { img, x }
+        Self { img, mask, x }
     }
 
     /// If the velocity is negative (in our case), the obstacle moves to the left.
@@ -130,35 +380,50 @@ where
 {
     pub buffer: Queue<Obstacle, BUFF_SIZE>,
     pub rng: R,
+    pub difficulty: DifficultyConfig,
+    layout: Layout,
 }
 
 impl<R> Obstacles<R>
 where
     R: super::rng::Rng,
 {
-    pub fn new(rng: R) -> Self {
-        let mut buffer = Queue::new();
-        buffer
-            .enqueue(Obstacle::new(&RAW_CACTUS1, OLED_WIDTH, CACTUS_Y))
+    pub fn new(rng: R, difficulty: DifficultyConfig, layout: Layout) -> Self {
+        let mut obstacles = Obstacles {
+            rng,
+            difficulty,
+            layout,
+            buffer: Queue::new(),
+        };
+        obstacles.reset();
+        obstacles
+    }
+
+    /// Puts the obstacles back to their starting positions, keeping the rng.
+    pub fn reset(&mut self) {
+        while self.buffer.dequeue().is_some() {}
+        let gap = self.random_gap(self.difficulty.start_speed);
+        let width = self.layout.width;
+        let [(cactus1, mask1, y1), (cactus2, mask2, y2), ..] = &self.sprites().obstacles;
+        let (y1, y2) = (self.layout.sprite_y(*y1), self.layout.sprite_y(*y2));
+        self.buffer
+            .enqueue(Obstacle::new(cactus1, mask1, width, y1))
             .unwrap();
-        buffer
-            .enqueue(Obstacle::new(
-                &RAW_CACTUS2,
-                OLED_WIDTH + (OBSTACLE_GAP),
-                CACTUS_Y,
-            ))
+        self.buffer
+            .enqueue(Obstacle::new(cactus2, mask2, width + gap, y2))
             .unwrap();
-
-        Obstacles { rng, buffer }
     }
 
     pub fn get_current(&self) -> &Queue<Obstacle, BUFF_SIZE> {
         &self.buffer
     }
 
-    pub fn update_state(&mut self) -> bool {
+    /// Moves the obstacles, `velocity` being negative as they move to the left.
+    ///
+    /// Returns `true` when an obstacle has left the screen and a new one was spawned.
+    pub fn update_state(&mut self, velocity: i32) -> bool {
         for obstacle in self.buffer.iter_mut() {
-            obstacle.move_by_velocity(OBSTACLE_VELOCITY);
+            obstacle.move_by_velocity(velocity);
         }
 
         let mut new_cactus = false;
@@ -168,19 +433,29 @@ where
                 new_cactus = true;
                 // Remove the first obstacle and add a new one at the end
                 self.buffer.dequeue();
-                let obs_idx = self.get_random_num(3) as usize;
-                self.buffer
-                    .enqueue(Obstacle::new(
-                        &OBSTACLES[obs_idx],
-                        OLED_WIDTH + OBSTACLE_GAP,
-                        35,
-                    ))
-                    .ok();
+                let obstacles = &self.sprites().obstacles;
+                let obs_idx = self.get_random_num(obstacles.len() as u32) as usize;
+                let (raw_img, mask, y) = &obstacles[obs_idx];
+                // The gap is measured from the last obstacle, but it never spawns on screen
+                let last_x = self.buffer.iter().last().map_or(0, |obs| obs.x);
+                let x = (last_x + self.random_gap(-velocity)).max(self.layout.width);
+                let y = self.layout.sprite_y(*y);
+                self.buffer.enqueue(Obstacle::new(raw_img, mask, x, y)).ok();
             }
         }
         new_cactus
     }
 
+    fn sprites(&self) -> &'static SpriteSet {
+        SpriteSet::for_scale(self.layout.scale)
+    }
+
+    /// Picks a gap in the jumpable range of the difficulty config.
+    fn random_gap(&mut self, speed: i32) -> i32 {
+        let (min, max) = self.difficulty.gap_range(speed);
+        min + self.get_random_num((max - min + 1) as u32) as i32
+    }
+
     /// Generates a random number within a specified limit.
     ///
     /// This function uses the `RoscRng` (Ring Oscillator Random Number Generator)
@@ -204,26 +479,222 @@ where
 pub struct Ground {
     pub img: Image<'static, ImgRawType>,
     position: Point,
+    // Right edge of the display, the ground is scrolled back before reaching it
+    display_width: i32,
 }
 
-impl Default for Ground {
-    fn default() -> Self {
-        let position = Point::new(GROUND_X_START, GROUND_Y);
+impl Ground {
+    pub fn new(layout: &Layout) -> Self {
+        let position = Point::new(GROUND_X_START, layout.ground_y);
         let image = Image::new(&RAW_GROUND, position);
         Self {
             img: image,
             position,
+            display_width: layout.width,
         }
     }
-}
 
-impl Ground {
     /// If the velocity is negative (in our case), the ground moves to the left.
     pub fn move_by_velocity(&mut self, velocity: i32) {
         self.position.x += velocity;
-        if self.position.x < (OLED_WIDTH - GROUND_X_LENGTH) {
+        if self.position.x < (self.display_width - GROUND_X_LENGTH) {
             self.position.x = GROUND_X_START;
         }
         self.img = Image::new(&RAW_GROUND, self.position);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SeededRng;
+    use embedded_graphics::prelude::OriginDimensions;
+
+    const GRAVITY: i32 = 7;
+
+    fn jump_arc(trex: &mut Trex) -> ([i32; 16], usize) {
+        let mut arc = [0; 16];
+        let mut frames = 0;
+        while trex.state != TrexState::Running {
+            trex.update_state();
+            arc[frames] = trex.position.y;
+            frames += 1;
+        }
+        (arc, frames)
+    }
+
+    #[test]
+    fn full_jump_goes_up_to_min_y_and_lands() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        trex.state = TrexState::Jumping;
+
+        let (arc, frames) = jump_arc(&mut trex);
+        assert_eq!(&arc[..frames], &[22, 15, 8, 3, 10, 17, 24, 29]);
+        assert_eq!(trex.img.bounding_box().top_left.y, TREX_GROUND_Y);
+    }
+
+    #[test]
+    fn released_jump_is_a_hop() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        assert!(trex.jump());
+        trex.release_jump();
+        let (arc, frames) = jump_arc(&mut trex);
+        assert_eq!(&arc[..frames], &[15, 22, 29]);
+
+        // Released above the hop height, the jump stops going up right away
+        assert!(trex.jump());
+        trex.update_state();
+        trex.release_jump();
+        let (arc, frames) = jump_arc(&mut trex);
+        assert_eq!(&arc[..frames], &[8, 15, 22, 29]);
+    }
+
+    #[test]
+    fn ducking_in_the_air_falls_right_away() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        trex.state = TrexState::Jumping;
+        trex.update_state();
+        trex.duck(true);
+
+        let (arc, frames) = jump_arc(&mut trex);
+        assert_eq!(&arc[..frames], &[29]);
+    }
+
+    #[test]
+    fn jump_heights_follow_the_ground() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y - 20, GRAVITY);
+        assert!(trex.jump());
+        let (arc, frames) = jump_arc(&mut trex);
+        assert_eq!(&arc[..frames], &[-5, -12, -17, -10, -3, 4, 9]);
+
+        trex.duck(true);
+        assert_eq!(trex.hitbox().top_left.y, TREX_DUCK_Y - 20);
+    }
+
+    #[test]
+    fn ducking_lowers_the_hitbox() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        let standing = trex.hitbox();
+
+        trex.duck(true);
+        assert_eq!(trex.state, TrexState::Ducking);
+        let ducking = trex.hitbox();
+        assert_eq!(ducking.top_left.y, TREX_DUCK_Y);
+        assert!(ducking.size.height < standing.size.height);
+        // Feet stay on the ground
+        assert_eq!(
+            ducking.bottom_right().unwrap().y,
+            standing.bottom_right().unwrap().y
+        );
+
+        trex.duck(false);
+        assert_eq!(trex.hitbox(), standing);
+    }
+
+    #[test]
+    fn masks_match_the_raw_images() {
+        for sprites in [&FULL_HEIGHT, &HALF_HEIGHT] {
+            let trex = [&sprites.trex_air]
+                .into_iter()
+                .chain(&sprites.trex_run)
+                .chain(&sprites.trex_duck);
+            for (raw, mask) in trex {
+                assert_eq!(mask.size(), raw.size());
+            }
+            for (raw, mask, _) in &sprites.obstacles {
+                assert_eq!(mask.size(), raw.size());
+            }
+        }
+    }
+
+    #[test]
+    fn generated_sprites_have_the_size_of_their_image() {
+        use embedded_graphics::prelude::Size;
+
+        assert_eq!(RAW_TREX.size(), Size::new(TREX_WIDTH, TREX_HEIGHT));
+        assert_eq!(RAW_TREX.size(), Size::new(25, 26));
+        // Sprite sheets are split in frames
+        assert_eq!(RAW_TREX_RUN.len(), TREX_RUN_FRAMES);
+        assert_eq!(RAW_TREX_RUN[1].size(), RAW_TREX.size());
+        assert_eq!(RAW_TREX_DUCK[1].size(), Size::new(34, 15));
+        assert_ne!(SPRITE_TREX_RUN[0], SPRITE_TREX_RUN[1]);
+        assert_eq!(SPRITE_TREX_RUN[0], SPRITE_TREX);
+        assert_eq!(RAW_PTERO.size(), Size::new(24, 14));
+        assert_eq!(RAW_GROUND.size().width as i32, GROUND_X_LENGTH);
+
+        // Half height copies, with the rows merged two by two
+        assert_eq!(RAW_TREX_HALF.size(), Size::new(25, 13));
+        assert_eq!(RAW_CACTUS1_HALF.size(), Size::new(11, 12));
+        assert_eq!(RAW_TREX_DUCK_HALF[1].size(), Size::new(34, 8));
+        assert_eq!(SPRITE_TREX_HALF[..4], [0x00, 0x7f, 0xe0, 0x00]);
+    }
+
+    #[test]
+    fn legs_move_on_the_ground_only() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        let mut frames = [0; 5];
+        for frame in &mut frames {
+            trex.update_state();
+            *frame = trex.running.frame_index();
+        }
+        assert_eq!(frames, [0, 1, 1, 0, 0]);
+
+        trex.duck(true);
+        for _ in 0..TREX_DUCK_FRAME_TICKS {
+            trex.update_state();
+        }
+        assert_eq!(trex.ducking.frame_index(), 1);
+        assert_eq!(trex.running.frame_index(), 0);
+
+        trex.duck(false);
+        assert!(trex.jump());
+        for _ in 0..3 {
+            trex.update_state();
+        }
+        assert_eq!(trex.running.frame_index(), 0);
+        assert_eq!(trex.ducking.frame_index(), 1);
+    }
+
+    #[test]
+    fn trex_mask_follows_its_state() {
+        let mut trex = Trex::new(TREX_X, TREX_GROUND_Y, GRAVITY);
+        assert_eq!(trex.mask().size(), trex.hitbox().size);
+        trex.duck(true);
+        assert_eq!(trex.mask().size(), trex.hitbox().size);
+    }
+
+    #[test]
+    fn recycled_obstacles_spawn_off_screen_within_the_gap_range() {
+        let difficulty = DifficultyConfig::default();
+        let speed = difficulty.start_speed;
+        let (min_gap, max_gap) = difficulty.gap_range(speed);
+        let layout = Layout::default();
+        let mut obstacles = Obstacles::new(SeededRng::new(9), difficulty, layout);
+
+        let mut recycled = 0;
+        for _ in 0..1000 {
+            let last_x = obstacles.buffer.iter().last().unwrap().x;
+            if obstacles.update_state(-speed) {
+                recycled += 1;
+                let new = obstacles.buffer.iter().last().unwrap();
+                let gap = new.x - (last_x - speed);
+                assert!(new.x >= layout.width);
+                assert!(gap >= min_gap && (new.x == layout.width || gap <= max_gap));
+            }
+            assert_eq!(obstacles.buffer.len(), 2);
+        }
+        assert!(recycled > 0);
+    }
+
+    #[test]
+    fn reset_puts_the_first_obstacle_at_the_screen_edge() {
+        let layout = Layout::default();
+        let mut obstacles = Obstacles::new(SeededRng::new(9), DifficultyConfig::default(), layout);
+        for _ in 0..100 {
+            obstacles.update_state(-12);
+        }
+        obstacles.reset();
+        assert_eq!(obstacles.buffer.len(), 2);
+        assert_eq!(obstacles.buffer.peek().unwrap().x, layout.width);
+    }
+}
@@ -0,0 +1,118 @@
+// Most updates run in a row when rendering fell behind, the remaining time is dropped
+// so a long stall doesn't make the game fast-forward
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Fixed-timestep clock with an accumulator.
+///
+/// Tells how many updates of `step_ms` are due since the last call, so the speed
+/// of the game doesn't depend on how long the display takes to flush.
+#[derive(Debug)]
+pub struct FixedTimestep {
+    step_ms: u64,
+    last_ms: u64,
+    accumulator_ms: u64,
+}
+
+impl FixedTimestep {
+    pub fn new(step_ms: u64, now_ms: u64) -> Self {
+        Self {
+            step_ms: step_ms.max(1),
+            last_ms: now_ms,
+            accumulator_ms: 0,
+        }
+    }
+
+    /// Returns the number of updates to run, `now_ms` being a monotonic timestamp.
+    pub fn advance(&mut self, now_ms: u64) -> u32 {
+        self.accumulator_ms += now_ms.saturating_sub(self.last_ms);
+        self.last_ms = now_ms;
+
+        let steps = self.accumulator_ms / self.step_ms;
+        self.accumulator_ms %= self.step_ms;
+        (steps as u32).min(MAX_STEPS_PER_FRAME)
+    }
+
+    /// Time left until the next update is due.
+    pub fn until_next_step_ms(&self) -> u64 {
+        self.step_ms - self.accumulator_ms
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameReport {
+    pub fps: u32,
+    /// Average time spent rendering and flushing a frame.
+    pub frame_time_ms: u32,
+}
+
+/// Counts the rendered frames and reports the frame rate once per second.
+#[derive(Debug)]
+pub struct FrameStats {
+    window_start_ms: u64,
+    frames: u32,
+    busy_ms: u64,
+}
+
+impl FrameStats {
+    pub fn new(now_ms: u64) -> Self {
+        Self {
+            window_start_ms: now_ms,
+            frames: 0,
+            busy_ms: 0,
+        }
+    }
+
+    /// Records a frame rendered between `start_ms` and `end_ms`.
+    ///
+    /// Returns a report when a second has passed since the last one.
+    pub fn frame_rendered(&mut self, start_ms: u64, end_ms: u64) -> Option<FrameReport> {
+        self.frames += 1;
+        self.busy_ms += end_ms.saturating_sub(start_ms);
+
+        let elapsed = end_ms.saturating_sub(self.window_start_ms);
+        if elapsed < 1000 {
+            return None;
+        }
+        let report = FrameReport {
+            fps: (self.frames as u64 * 1000 / elapsed) as u32,
+            frame_time_ms: (self.busy_ms / self.frames as u64) as u32,
+        };
+        *self = Self::new(end_ms);
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_follow_the_elapsed_time() {
+        let mut timestep = FixedTimestep::new(60, 1000);
+        assert_eq!(timestep.advance(1030), 0);
+        assert_eq!(timestep.until_next_step_ms(), 30);
+        assert_eq!(timestep.advance(1070), 1);
+        assert_eq!(timestep.advance(1200), 2);
+        assert_eq!(timestep.until_next_step_ms(), 40);
+    }
+
+    #[test]
+    fn long_stalls_are_not_caught_up() {
+        let mut timestep = FixedTimestep::new(60, 0);
+        assert_eq!(timestep.advance(10_000), MAX_STEPS_PER_FRAME);
+        assert_eq!(timestep.advance(10_010), 0);
+    }
+
+    #[test]
+    fn frame_rate_is_reported_every_second() {
+        let mut stats = FrameStats::new(0);
+        for frame in 0..9 {
+            let start = frame * 100;
+            assert_eq!(stats.frame_rendered(start, start + 80), None);
+        }
+        let report = stats.frame_rendered(900, 1000).unwrap();
+        assert_eq!(report.fps, 10);
+        assert_eq!(report.frame_time_ms, 82);
+        assert_eq!(stats.frame_rendered(1010, 1090), None);
+    }
+}
@@ -0,0 +1,627 @@
+//! International Morse code, as in ITU-R M.1677-1.
+//!
+//! `Encoder` turns text into the on and off `Element`s to key a buzzer or a LED
+//! with, in units of the dot length, and `Decoder` turns them back into text.
+//! `Timing` converts the units to milliseconds and measured milliseconds to units:
+//!
+//! ```ignore
+//! let timing = Timing::from_wpm(12);
+//! for element in morse::encode("SOS <SK>") {
+//!     buzzer.set_level(element.on.into());
+//!     delay.delay_millis(timing.millis(element));
+//! }
+//! ```
+//!
+//! Letters are case insensitive, prosigns are written between angle brackets
+//! and characters without a code are skipped.
+#![cfg_attr(not(test), no_std)]
+
+use core::fmt;
+use core::str::Chars;
+
+/// Units of a dot, the unit of all the timing.
+pub const DOT: u8 = 1;
+/// Units of a dash.
+pub const DASH: u8 = 3;
+/// Silence between the elements of a letter.
+pub const ELEMENT_GAP: u8 = 1;
+/// Silence between the letters of a word.
+pub const LETTER_GAP: u8 = 3;
+/// Silence between words.
+pub const WORD_GAP: u8 = 7;
+
+/// A dot or a dash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Dot,
+    Dash,
+}
+
+impl Mark {
+    pub const fn units(self) -> u8 {
+        match self {
+            Mark::Dot => DOT,
+            Mark::Dash => DASH,
+        }
+    }
+}
+
+/// Dots and dashes of a letter or a prosign, up to `Code::MAX_LEN`.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Code {
+    // One bit per mark, set for a dash, the first mark in the highest used bit
+    bits: u16,
+    len: u8,
+}
+
+impl Code {
+    pub const MAX_LEN: usize = 16;
+
+    /// The code written with `.` and `-`, `None` with other characters or more
+    /// than `MAX_LEN` of them.
+    pub const fn parse(dots_and_dashes: &str) -> Option<Code> {
+        let bytes = dots_and_dashes.as_bytes();
+        if bytes.len() > Self::MAX_LEN {
+            return None;
+        }
+        let mut code = Code { bits: 0, len: 0 };
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let mark = match bytes[idx] {
+                b'.' => Mark::Dot,
+                b'-' => Mark::Dash,
+                _ => return None,
+            };
+            code = match code.push(mark) {
+                Some(code) => code,
+                None => return None,
+            };
+            idx += 1;
+        }
+        Some(code)
+    }
+
+    pub const fn len(self) -> usize {
+        self.len as usize
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.len == 0
+    }
+
+    /// The code with `mark` added at the end, `None` once full.
+    pub const fn push(self, mark: Mark) -> Option<Code> {
+        if self.len() == Self::MAX_LEN {
+            return None;
+        }
+        Some(Code {
+            bits: self.bits << 1 | matches!(mark, Mark::Dash) as u16,
+            len: self.len + 1,
+        })
+    }
+
+    /// The first mark and the code of the others.
+    pub const fn split_first(self) -> Option<(Mark, Code)> {
+        if self.len == 0 {
+            return None;
+        }
+        let len = self.len - 1;
+        let mark = if self.bits >> len & 1 == 1 {
+            Mark::Dash
+        } else {
+            Mark::Dot
+        };
+        let rest = Code {
+            bits: self.bits & ((1 << len) - 1),
+            len,
+        };
+        Some((mark, rest))
+    }
+
+    pub fn marks(self) -> impl Iterator<Item = Mark> {
+        let mut rest = self;
+        core::iter::from_fn(move || {
+            let (mark, others) = rest.split_first()?;
+            rest = others;
+            Some(mark)
+        })
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.marks().try_for_each(|mark| match mark {
+            Mark::Dot => f.write_str("."),
+            Mark::Dash => f.write_str("-"),
+        })
+    }
+}
+
+impl fmt::Debug for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Code({self})")
+    }
+}
+
+const fn code(dots_and_dashes: &str) -> Code {
+    match Code::parse(dots_and_dashes) {
+        Some(code) => code,
+        None => panic!("not a Morse code"),
+    }
+}
+
+// The letters, figures and punctuation of ITU-R M.1677-1, then the usual
+// amateur radio ones it doesn't have
+const CHARACTERS: [(char, Code); 54] = [
+    ('A', code(".-")),
+    ('B', code("-...")),
+    ('C', code("-.-.")),
+    ('D', code("-..")),
+    ('E', code(".")),
+    ('F', code("..-.")),
+    ('G', code("--.")),
+    ('H', code("....")),
+    ('I', code("..")),
+    ('J', code(".---")),
+    ('K', code("-.-")),
+    ('L', code(".-..")),
+    ('M', code("--")),
+    ('N', code("-.")),
+    ('O', code("---")),
+    ('P', code(".--.")),
+    ('Q', code("--.-")),
+    ('R', code(".-.")),
+    ('S', code("...")),
+    ('T', code("-")),
+    ('U', code("..-")),
+    ('V', code("...-")),
+    ('W', code(".--")),
+    ('X', code("-..-")),
+    ('Y', code("-.--")),
+    ('Z', code("--..")),
+    ('1', code(".----")),
+    ('2', code("..---")),
+    ('3', code("...--")),
+    ('4', code("....-")),
+    ('5', code(".....")),
+    ('6', code("-....")),
+    ('7', code("--...")),
+    ('8', code("---..")),
+    ('9', code("----.")),
+    ('0', code("-----")),
+    ('.', code(".-.-.-")),
+    (',', code("--..--")),
+    (':', code("---...")),
+    ('?', code("..--..")),
+    ('\'', code(".----.")),
+    ('-', code("-....-")),
+    ('/', code("-..-.")),
+    ('(', code("-.--.")),
+    (')', code("-.--.-")),
+    ('"', code(".-..-.")),
+    ('=', code("-...-")),
+    ('+', code(".-.-.")),
+    ('@', code(".--.-.")),
+    ('!', code("-.-.--")),
+    ('&', code(".-...")),
+    (';', code("-.-.-.")),
+    ('_', code("..--.-")),
+    ('$', code("...-..-")),
+];
+
+/// Code of `c`, whatever its case.
+pub fn encode_char(c: char) -> Option<Code> {
+    let c = c.to_ascii_uppercase();
+    CHARACTERS
+        .iter()
+        .find(|&&(character, _)| character == c)
+        .map(|&(_, code)| code)
+}
+
+/// Procedure signals, letters sent without the gap between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prosign {
+    /// End of message, the code of `+`.
+    AR,
+    /// Wait, the code of `&`.
+    AS,
+    /// Break between two parts of a message, the code of `=`.
+    BT,
+    /// Starting signal.
+    CT,
+    /// Error, eight dots.
+    HH,
+    /// Go ahead, only the station called, the code of `(`.
+    KN,
+    /// End of work.
+    SK,
+    /// Understood.
+    SN,
+    /// Distress.
+    SOS,
+}
+
+const PROSIGNS: [(Prosign, &str, Code); 9] = [
+    (Prosign::AR, "AR", code(".-.-.")),
+    (Prosign::AS, "AS", code(".-...")),
+    (Prosign::BT, "BT", code("-...-")),
+    (Prosign::CT, "CT", code("-.-.-")),
+    (Prosign::HH, "HH", code("........")),
+    (Prosign::KN, "KN", code("-.--.")),
+    (Prosign::SK, "SK", code("...-.-")),
+    (Prosign::SN, "SN", code("...-.")),
+    (Prosign::SOS, "SOS", code("...---...")),
+];
+
+impl Prosign {
+    /// The prosign written `name`, whatever its case.
+    pub fn from_name(name: &str) -> Option<Prosign> {
+        PROSIGNS
+            .iter()
+            .find(|(_, known, _)| known.eq_ignore_ascii_case(name))
+            .map(|&(prosign, _, _)| prosign)
+    }
+
+    pub fn name(self) -> &'static str {
+        PROSIGNS[self as usize].1
+    }
+
+    pub fn code(self) -> Code {
+        PROSIGNS[self as usize].2
+    }
+}
+
+impl fmt::Display for Prosign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}>", self.name())
+    }
+}
+
+/// What a `Code` stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    Char(char),
+    Prosign(Prosign),
+    /// The gap between two words.
+    Space,
+    /// A code that is neither a character nor a prosign.
+    Unknown,
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Symbol::Char(c) => write!(f, "{c}"),
+            Symbol::Prosign(prosign) => write!(f, "{prosign}"),
+            Symbol::Space => f.write_str(" "),
+            Symbol::Unknown => f.write_str("<?>"),
+        }
+    }
+}
+
+/// The character of `code`, or else its prosign. AR, AS, BT and KN share their
+/// code with `+`, `&`, `=` and `(` and are decoded as those.
+pub fn decode(code: Code) -> Symbol {
+    if let Some(&(c, _)) = CHARACTERS.iter().find(|&&(_, known)| known == code) {
+        return Symbol::Char(c);
+    }
+    PROSIGNS
+        .iter()
+        .find(|&&(_, _, known)| known == code)
+        .map_or(Symbol::Unknown, |&(prosign, _, _)| Symbol::Prosign(prosign))
+}
+
+/// Keying on or off, for `units` dot lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Element {
+    pub on: bool,
+    pub units: u8,
+}
+
+impl Element {
+    pub const fn on(units: u8) -> Self {
+        Self { on: true, units }
+    }
+
+    pub const fn off(units: u8) -> Self {
+        Self { on: false, units }
+    }
+}
+
+/// Length of the dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub unit_ms: u32,
+}
+
+impl Timing {
+    pub const fn from_unit_ms(unit_ms: u32) -> Self {
+        Self { unit_ms }
+    }
+
+    /// The speed in words per minute of "PARIS ", 50 units long.
+    pub const fn from_wpm(wpm: u32) -> Self {
+        Self {
+            unit_ms: 1200 / wpm,
+        }
+    }
+
+    pub const fn millis(&self, element: Element) -> u32 {
+        element.units as u32 * self.unit_ms
+    }
+
+    /// Element measured on or off for `ms`, rounded to whole units and at
+    /// least one.
+    pub fn element(&self, on: bool, ms: u32) -> Element {
+        let units = (ms + self.unit_ms / 2) / self.unit_ms.max(1);
+        Element {
+            on,
+            units: units.clamp(1, u8::MAX.into()) as u8,
+        }
+    }
+}
+
+/// Elements of `text`, see `Encoder`.
+pub fn encode(text: &str) -> Encoder<'_> {
+    Encoder::new(text)
+}
+
+/// The elements keying `text`, without silence before the first mark or after
+/// the last one.
+#[derive(Debug, Clone)]
+pub struct Encoder<'a> {
+    text: Chars<'a>,
+    // Marks left of the letter being sent
+    letter: Code,
+    // Silence owed before the next mark, `None` until the first one
+    gap: Option<u8>,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text: text.chars(),
+            letter: Code::default(),
+            gap: None,
+        }
+    }
+
+    // Code of the next letter or prosign, skips what has no code
+    fn next_code(&mut self) -> Option<Code> {
+        loop {
+            let c = self.text.next()?;
+            if c.is_whitespace() {
+                if let Some(gap) = &mut self.gap {
+                    *gap = (*gap).max(WORD_GAP);
+                }
+            } else if c == '<' {
+                let name = self.text.as_str();
+                let Some(end) = name.find('>') else {
+                    continue;
+                };
+                self.text = name[end + 1..].chars();
+                if let Some(prosign) = Prosign::from_name(&name[..end]) {
+                    return Some(prosign.code());
+                }
+            } else if let Some(code) = encode_char(c) {
+                return Some(code);
+            }
+        }
+    }
+}
+
+impl Iterator for Encoder<'_> {
+    type Item = Element;
+
+    fn next(&mut self) -> Option<Element> {
+        if self.letter.is_empty() {
+            self.letter = self.next_code()?;
+        }
+        if let Some(gap) = self.gap.filter(|&gap| gap > 0) {
+            self.gap = Some(0);
+            return Some(Element::off(gap));
+        }
+        let (mark, rest) = self.letter.split_first()?;
+        self.letter = rest;
+        self.gap = Some(if rest.is_empty() {
+            LETTER_GAP
+        } else {
+            ELEMENT_GAP
+        });
+        Some(Element::on(mark.units()))
+    }
+}
+
+/// Turns elements back into symbols. Marks of 2 units or more are dashes,
+/// silences of 2 units end a letter and of 5 units a word, so that keying by
+/// hand with uneven timing is still read.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    // Marks of the letter so far, `None` once longer than any code
+    letter: Option<Code>,
+    started: bool,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the next element, passing the symbols it ends to `output`.
+    pub fn push(&mut self, element: Element, mut output: impl FnMut(Symbol)) {
+        if element.on {
+            let mark = if element.units >= 2 {
+                Mark::Dash
+            } else {
+                Mark::Dot
+            };
+            self.letter = match (self.started, self.letter) {
+                (false, _) => Code::default().push(mark),
+                (true, letter) => letter.and_then(|letter| letter.push(mark)),
+            };
+            self.started = true;
+        } else if element.units >= 5 {
+            if self.finish(&mut output) {
+                output(Symbol::Space);
+            }
+        } else if element.units >= 2 {
+            self.finish(output);
+        }
+    }
+
+    /// Passes the letter being received to `output`, at the end of the
+    /// transmission. Returns whether there was one.
+    pub fn finish(&mut self, mut output: impl FnMut(Symbol)) -> bool {
+        if !self.started {
+            return false;
+        }
+        self.started = false;
+        output(self.letter.map_or(Symbol::Unknown, decode));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(text: &str) -> Vec<(bool, u8)> {
+        encode(text)
+            .map(|element| (element.on, element.units))
+            .collect()
+    }
+
+    fn decoded(elements: impl IntoIterator<Item = Element>) -> String {
+        let mut decoder = Decoder::new();
+        let mut text = String::new();
+        for element in elements {
+            decoder.push(element, |symbol| text += &symbol.to_string());
+        }
+        decoder.finish(|symbol| text += &symbol.to_string());
+        text
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        for (idx, &(c, code)) in CHARACTERS.iter().enumerate() {
+            assert!(
+                CHARACTERS[idx + 1..]
+                    .iter()
+                    .all(|&(_, other)| other != code),
+                "{c}"
+            );
+            assert_eq!(decode(code), Symbol::Char(c));
+        }
+        for (idx, &(prosign, _, code)) in PROSIGNS.iter().enumerate() {
+            assert_eq!(prosign as usize, idx);
+            assert_eq!(prosign.code(), code);
+        }
+    }
+
+    #[test]
+    fn code_parse_and_display() {
+        let code = Code::parse("-.--.").unwrap();
+        assert_eq!(code.len(), 5);
+        assert_eq!(code.to_string(), "-.--.");
+        assert_eq!(
+            code.marks().collect::<Vec<_>>(),
+            [Mark::Dash, Mark::Dot, Mark::Dash, Mark::Dash, Mark::Dot]
+        );
+        assert_eq!(Code::parse(""), Some(Code::default()));
+        assert_eq!(Code::parse(".-x"), None);
+        assert!(Code::parse(&"-".repeat(16)).is_some());
+        assert_eq!(Code::parse(&"-".repeat(17)), None);
+        assert_eq!(
+            encode_char('q').map(|code| code.to_string()).as_deref(),
+            Some("--.-")
+        );
+        assert_eq!(encode_char('#'), None);
+    }
+
+    #[test]
+    fn encodes_with_the_standard_gaps() {
+        // A is .- and I is .., 50 units in all for PARIS with its word gap
+        assert_eq!(
+            elements("a  i"),
+            [
+                (true, 1),
+                (false, 1),
+                (true, 3),
+                (false, 7),
+                (true, 1),
+                (false, 1),
+                (true, 1)
+            ]
+        );
+        let paris: u32 = encode("PARIS")
+            .map(|element| u32::from(element.units))
+            .sum();
+        assert_eq!(paris + u32::from(WORD_GAP), 50);
+        assert_eq!(elements(" \tE "), [(true, 1)]);
+        assert_eq!(elements("#"), []);
+        assert_eq!(elements("E#E"), elements("EE"));
+    }
+
+    #[test]
+    fn prosigns_are_sent_as_one_letter() {
+        let sk: Vec<_> = Prosign::SK
+            .code()
+            .marks()
+            .map(|mark| mark.units())
+            .collect();
+        assert_eq!(sk, [1, 1, 1, 3, 1, 3]);
+        assert_eq!(elements("<sk>").len(), 11);
+        assert_eq!(elements("E<SK>")[..3], [(true, 1), (false, 3), (true, 1)]);
+        assert_eq!(elements("<XX>E"), elements("E"));
+        assert_eq!(elements("<SK"), elements("SK"));
+        assert_eq!(Prosign::from_name("sos"), Some(Prosign::SOS));
+        assert_eq!(Prosign::SOS.to_string(), "<SOS>");
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        let text = "CQ DE EA1ABC, 73! <SK> <SOS> (\"QRL?\")";
+        assert_eq!(
+            decoded(encode(text)),
+            "CQ DE EA1ABC, 73! <SK> <SOS> (\"QRL?\")"
+        );
+        // Prosigns with a character of their own come back as the character
+        assert_eq!(decoded(encode("<AR> <BT>")), "+ =");
+        assert_eq!(decoded(encode("")), "");
+    }
+
+    #[test]
+    fn decodes_uneven_timing() {
+        let timing = Timing::from_wpm(10);
+        assert_eq!(timing.unit_ms, 120);
+        // "TE ST" keyed by hand
+        let keyed = [
+            (true, 300),
+            (false, 250),
+            (true, 150),
+            (false, 700),
+            (true, 90),
+            (false, 100),
+            (true, 130),
+            (false, 160),
+            (true, 110),
+            (false, 290),
+            (true, 400),
+        ];
+        let elements = keyed.map(|(on, ms)| timing.element(on, ms));
+        assert_eq!(elements[0], Element::on(3));
+        assert_eq!(timing.millis(elements[0]), 360);
+        assert_eq!(decoded(elements), "TE ST");
+    }
+
+    #[test]
+    fn unknown_codes() {
+        let too_long = [Element::on(1), Element::off(1)].repeat(20);
+        assert_eq!(decoded(too_long), "<?>");
+        assert_eq!(decoded([Element::on(3); 6]), "<?>");
+        assert_eq!(
+            decoded([Element::off(7), Element::on(1), Element::off(7)]),
+            "E "
+        );
+    }
+}
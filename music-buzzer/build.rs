@@ -1,9 +1,408 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::{env, fs};
+
 fn main() {
+    generate_songs();
+    generate_wavetable();
+
+    // Unit tests are built for the host, which links without the ESP32 linker scripts
+    if std::env::var("CARGO_CFG_TARGET_ARCH").is_ok_and(|arch| arch != "xtensa") {
+        return;
+    }
     linker_be_nice();
+    println!("cargo:rustc-link-arg=-Tdefmt.x");
     // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
     println!("cargo:rustc-link-arg=-Tlinkall.x");
 }
 
+/// Converts the Standard MIDI files of `songs/` into `$OUT_DIR/songs.rs`.
+///
+/// `songs/ode-to-joy.mid` becomes the module `ode_to_joy` with a `TEMPO` and a
+/// `MELODY` like `pink_panther`. A buzzer plays one note at a time, so the melody is
+/// the highest note sounding on all the channels but the percussion one, or the
+/// highest note of the channel given in `songs/channels.txt`.
+///
+/// The notes are rounded to 32nd notes and their length to the plain and dotted
+/// durations of `Song::calc_note_duration`, longer notes are split. Only the first
+/// tempo of the file is kept.
+fn generate_songs() {
+    let songs = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("songs");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", songs.display());
+
+    let channels = fs::read_to_string(songs.join("channels.txt"))
+        .map(|text| song_channels(&text).unwrap_or_else(|err| panic!("songs/channels.txt: {err}")))
+        .unwrap_or_default();
+
+    let mut paths: Vec<_> = fs::read_dir(&songs)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "mid"))
+        .collect();
+    paths.sort();
+
+    let mut out =
+        String::from("// Generated by build.rs from the MIDI files in songs/, do not edit\n");
+    for path in paths {
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let stem = path.file_stem().unwrap().to_str().unwrap();
+        let channel = channels
+            .iter()
+            .find(|(song, _)| song == stem)
+            .map(|&(_, channel)| channel);
+        let midi = Midi::parse(&fs::read(&path).unwrap())
+            .unwrap_or_else(|err| panic!("songs/{file_name}: {err}"));
+        if midi.tempos().count() > 1 {
+            println!(
+                "cargo:warning=songs/{file_name} changes tempo, it is played at the first one"
+            );
+        }
+        let melody = midi.melody(channel);
+        if melody.is_empty() {
+            panic!("songs/{file_name}: no notes to play");
+        }
+
+        let source = match channel {
+            Some(channel) => format!("channel {channel}"),
+            None => "highest note".to_string(),
+        };
+        writeln!(out).unwrap();
+        writeln!(out, "/// {file_name}, {source}").unwrap();
+        writeln!(out, "pub mod {} {{", stem.to_lowercase().replace('-', "_")).unwrap();
+        writeln!(out, "    use crate::music::*;").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    pub const TEMPO: u16 = {};", midi.tempo_bpm()).unwrap();
+        writeln!(
+            out,
+            "    pub const MELODY: [(f64, i16); {}] = [",
+            melody.len()
+        )
+        .unwrap();
+        for (note, divider) in melody {
+            writeln!(out, "        ({note}, {divider}),").unwrap();
+        }
+        writeln!(out, "    ];").unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("songs.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Writes a period of a sine wave to `$OUT_DIR/wavetable.rs` for `synth`, `core`
+/// has no `sin` for the firmware to compute it.
+fn generate_wavetable() {
+    const LEN: usize = 256;
+    let mut out = String::from("// Generated by build.rs, do not edit\n");
+    writeln!(out, "const SINE: [i16; {LEN}] = [").unwrap();
+    for idx in 0..LEN {
+        let angle = idx as f64 / LEN as f64 * std::f64::consts::TAU;
+        writeln!(out, "    {},", (angle.sin() * f64::from(i16::MAX)).round()).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("wavetable.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Reads the `<song> <channel>` lines of `songs/channels.txt`, channels going from 1
+/// to 16 like in the music software.
+fn song_channels(text: &str) -> Result<Vec<(String, u8)>, String> {
+    let mut channels = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (song, channel) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("line {}: expected `<song> <channel>`", idx + 1))?;
+        let channel = channel
+            .trim()
+            .parse()
+            .ok()
+            .filter(|channel| (1..=16).contains(channel))
+            .ok_or_else(|| format!("line {}: the channel goes from 1 to 16", idx + 1))?;
+        channels.push((song.to_string(), channel));
+    }
+    Ok(channels)
+}
+
+// Microseconds per quarter note when a file sets no tempo, 120 bpm
+const DEFAULT_TEMPO_US: u32 = 500_000;
+// Channel 10, only used for percussion in General MIDI
+const PERCUSSION_CHANNEL: u8 = 9;
+// Lowest and highest notes of the `music::NOTE_*` constants, B0 and D#8
+const LOWEST_KEY: u8 = 23;
+const HIGHEST_KEY: u8 = 111;
+const NOTE_NAMES: [&str; 12] = [
+    "C", "CS", "D", "DS", "E", "F", "FS", "G", "GS", "A", "AS", "B",
+];
+// Lengths in 32nd notes that have a divider, the longest first
+const DIVIDERS: [(u64, i16); 11] = [
+    (48, -1),
+    (32, 1),
+    (24, -2),
+    (16, 2),
+    (12, -4),
+    (8, 4),
+    (6, -8),
+    (4, 8),
+    (3, -16),
+    (2, 16),
+    (1, 32),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EventKind {
+    // Sorted before the notes starting at the same time
+    NoteOff { channel: u8, key: u8 },
+    NoteOn { channel: u8, key: u8 },
+    Tempo(u32),
+}
+
+/// The events of all the tracks of a type 0 or type 1 file, in time order.
+struct Midi {
+    ticks_per_quarter: u64,
+    // (tick, event)
+    events: Vec<(u64, EventKind)>,
+}
+
+impl Midi {
+    fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let header = reader.chunk(b"MThd")?;
+        if header.len() < 6 {
+            return Err("truncated header".into());
+        }
+        let format = u16::from_be_bytes([header[0], header[1]]);
+        let tracks = u16::from_be_bytes([header[2], header[3]]);
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if format > 1 {
+            return Err(format!(
+                "type {format} files aren't supported, only types 0 and 1"
+            ));
+        }
+        if division & 0x8000 != 0 || division == 0 {
+            return Err("SMPTE timing isn't supported, only ticks per quarter note".into());
+        }
+
+        let mut events = Vec::new();
+        for _ in 0..tracks {
+            let track = reader.chunk(b"MTrk")?;
+            parse_track(track, &mut events)?;
+        }
+        // A note ends before the notes starting at the same time
+        events.sort_by_key(|&(tick, kind)| (tick, kind));
+        Ok(Self {
+            ticks_per_quarter: division.into(),
+            events,
+        })
+    }
+
+    fn tempos(&self) -> impl Iterator<Item = u32> + '_ {
+        self.events.iter().filter_map(|&(_, kind)| match kind {
+            EventKind::Tempo(us_per_quarter) => Some(us_per_quarter),
+            _ => None,
+        })
+    }
+
+    fn tempo_bpm(&self) -> u16 {
+        let us_per_quarter = self.tempos().next().unwrap_or(DEFAULT_TEMPO_US);
+        (60_000_000.0 / f64::from(us_per_quarter)).round().max(1.0) as u16
+    }
+
+    /// The `(note, divider)` pairs of the melody, `note` being the name of a
+    /// `music` constant.
+    fn melody(&self, channel: Option<u8>) -> Vec<(String, i16)> {
+        let plays = |event_channel: u8| match channel {
+            Some(channel) => event_channel == channel - 1,
+            None => event_channel != PERCUSSION_CHANNEL,
+        };
+
+        // Times the highest note changes, on the grid of 32nd notes. A note struck
+        // again is a change too.
+        let grid = (self.ticks_per_quarter / 8).max(1);
+        let mut changes: Vec<(u64, Option<u8>)> = vec![(0, None)];
+        let mut sounding = [0u32; 128];
+        let mut idx = 0;
+        while idx < self.events.len() {
+            let tick = self.events[idx].0;
+            let mut struck = [false; 128];
+            while let Some(&(_, kind)) = self.events.get(idx).filter(|event| event.0 == tick) {
+                match kind {
+                    EventKind::NoteOn { channel, key } if plays(channel) => {
+                        sounding[usize::from(key)] += 1;
+                        struck[usize::from(key)] = true;
+                    }
+                    EventKind::NoteOff { channel, key } if plays(channel) => {
+                        let count = &mut sounding[usize::from(key)];
+                        *count = count.saturating_sub(1);
+                    }
+                    _ => (),
+                }
+                idx += 1;
+            }
+
+            let highest = (0..128u8).rev().find(|&key| sounding[usize::from(key)] > 0);
+            let (_, current) = *changes.last().unwrap();
+            let restruck = highest.is_some_and(|key| struck[usize::from(key)]);
+            if highest == current && !restruck {
+                continue;
+            }
+            let at = (tick + grid / 2) / grid;
+            match changes.last_mut() {
+                // The previous note is shorter than the grid, it is dropped
+                Some(last) if last.0 == at => last.1 = highest,
+                _ => changes.push((at, highest)),
+            }
+        }
+        changes.dedup_by(|next, previous| next.1.is_none() && previous.1.is_none());
+
+        let mut melody = Vec::new();
+        for pair in changes.windows(2) {
+            let (start, key) = pair[0];
+            let note = match key {
+                Some(key) => note_name(key),
+                None => "REST".to_string(),
+            };
+            let mut length = pair[1].0 - start;
+            while length > 0 {
+                let &(piece, divider) = DIVIDERS
+                    .iter()
+                    .find(|&&(piece, _)| piece <= length)
+                    .unwrap();
+                melody.push((note.clone(), divider));
+                length -= piece;
+            }
+        }
+        melody
+    }
+}
+
+// Name of the `music` constant closest to a MIDI note, moved by octaves into
+// the range of the constants
+fn note_name(mut key: u8) -> String {
+    while key < LOWEST_KEY {
+        key += 12;
+    }
+    while key > HIGHEST_KEY {
+        key -= 12;
+    }
+    let octave = key / 12 - 1;
+    format!("NOTE_{}{octave}", NOTE_NAMES[usize::from(key % 12)])
+}
+
+fn parse_track(track: &[u8], events: &mut Vec<(u64, EventKind)>) -> Result<(), String> {
+    let mut reader = Reader {
+        bytes: track,
+        pos: 0,
+    };
+    let mut tick = 0;
+    let mut running_status = None;
+    while reader.pos < track.len() {
+        tick += u64::from(reader.var_len()?);
+        let mut status = reader.byte()?;
+        if status < 0x80 {
+            // Running status, the byte is the first data byte
+            status = running_status.ok_or("data byte without a status")?;
+            reader.pos -= 1;
+        }
+        match status {
+            0xff => {
+                let kind = reader.byte()?;
+                let len = reader.var_len()? as usize;
+                let data = reader.take(len)?;
+                match (kind, data) {
+                    (0x2f, _) => break,
+                    (0x51, &[a, b, c]) => {
+                        let us_per_quarter = u32::from_be_bytes([0, a, b, c]);
+                        events.push((tick, EventKind::Tempo(us_per_quarter)));
+                    }
+                    _ => (),
+                }
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.var_len()? as usize;
+                reader.take(len)?;
+            }
+            0xf1..=0xfe => return Err(format!("unexpected status {status:#04x}")),
+            _ => {
+                running_status = Some(status);
+                let channel = status & 0x0f;
+                let data_len = if matches!(status & 0xf0, 0xc0 | 0xd0) {
+                    1
+                } else {
+                    2
+                };
+                let data = reader.take(data_len)?;
+                match (status & 0xf0, data) {
+                    (0x90, &[key, velocity]) if velocity > 0 => {
+                        events.push((tick, EventKind::NoteOn { channel, key }));
+                    }
+                    // A note on with a velocity of 0 is a note off
+                    (0x80 | 0x90, &[key, _]) => {
+                        events.push((tick, EventKind::NoteOff { channel, key }));
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let data = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or("unexpected end of file")?;
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let data = self.take(4)?;
+        Ok(u32::from_be_bytes([data[0], data[1], data[2], data[3]]))
+    }
+
+    // Variable length quantity, 7 bits per byte with the top bit set on all but the last
+    fn var_len(&mut self) -> Result<u32, String> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("variable length quantity over 4 bytes".into())
+    }
+
+    // Data of the next chunk of type `id`, skipping the unknown chunks before it
+    fn chunk(&mut self, id: &[u8; 4]) -> Result<&'a [u8], String> {
+        loop {
+            let chunk_id = self.take(4)?;
+            let len = self.u32()? as usize;
+            let data = self.take(len)?;
+            if chunk_id == id {
+                return Ok(data);
+            }
+        }
+    }
+}
+
 fn linker_be_nice() {
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
@@ -0,0 +1,138 @@
+//! Renders a tune to a WAV file, or prints its timeline, to check it without
+//! flashing the board:
+//!
+//! ```text
+//! cargo +stable run --example render --target x86_64-unknown-linux-gnu -- pink_panther out.wav
+//! cargo +stable run --example render --target x86_64-unknown-linux-gnu -- nokia_tune --timeline
+//! cargo +stable run --example render --target x86_64-unknown-linux-gnu -- ringtone.txt out.wav
+//! cargo +stable run --example render --target x86_64-unknown-linux-gnu -- twinkle_duet out.wav --wave sine
+//! ```
+//!
+//! The tune is a track of the library, by its title in snake case, or a file
+//! holding an RTTTL ringtone. With `--wave` it is played by the synthesizer of
+//! the `dac` and `i2s` features rather than the buzzers.
+
+use music_buzzer::library::{Track, LIBRARY};
+use music_buzzer::player::{Command, Player, Tune};
+use music_buzzer::render::{self, Timed, Timeline};
+use music_buzzer::rtttl::Ringtone;
+use music_buzzer::synth::{Synth, Waveform};
+use std::io::{BufWriter, Write};
+use std::{env, fs, process};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+// Name of `track` on the command line, e.g. `ode_to_joy`
+fn track_name(track: &Track) -> String {
+    track.title.to_lowercase().replace(' ', "_")
+}
+
+fn tune(name: &str) -> Result<Tune, String> {
+    if let Some(track) = LIBRARY.iter().find(|track| track_name(track) == name) {
+        return Ok(track.tune());
+    }
+    let text = fs::read_to_string(name).map_err(|err| format!("{name}: {err}"))?;
+    // The tune lives as long as the tool
+    Ringtone::parse(text.leak())
+        .map(Tune::Ringtone)
+        .map_err(|err| format!("{name}: {err}"))
+}
+
+fn print_timeline(timeline: Timeline) {
+    println!("   start  part     on    off  frequency");
+    for timed in timeline {
+        let note = timed.note;
+        let pitch = if note.is_rest() {
+            "rest".to_string()
+        } else {
+            format!("{} Hz", note.frequency)
+        };
+        println!(
+            "{:6} ms {:5} {:6} {:6}  {pitch}",
+            timed.start_ms, timed.part, note.on_ms, note.off_ms
+        );
+    }
+}
+
+fn waveform(name: &str) -> Option<Waveform> {
+    match name {
+        "sine" => Some(Waveform::Sine),
+        "triangle" => Some(Waveform::Triangle),
+        "sawtooth" => Some(Waveform::Sawtooth),
+        "square" => Some(Waveform::Square),
+        _ => None,
+    }
+}
+
+// Samples of the square waves of the buzzers
+fn buzzer_samples(timeline: Timeline) -> Vec<i16> {
+    let notes: Vec<_> = timeline.collect();
+    // The parts of a duet may not end with the last note to start
+    let end_ms = notes.iter().map(Timed::end_ms).max().unwrap_or(0);
+    let mut samples = Vec::with_capacity(render::sample_count(end_ms, SAMPLE_RATE) as usize);
+    render::render(notes, SAMPLE_RATE, |sample| samples.push(sample));
+    samples
+}
+
+// Samples of the synthesizer, until the tune is over
+fn synth_samples(tune: Tune, waveform: Waveform) -> Vec<i16> {
+    let mut synth = Synth::new(SAMPLE_RATE, waveform);
+    let mut player = Player::new();
+    synth.handle(&mut player, Command::Play(tune));
+    let mut samples = Vec::new();
+    let mut chunk = [0; 1024];
+    while player.is_playing() {
+        synth.fill(&mut player, &mut chunk, |_| ());
+        samples.extend_from_slice(&chunk);
+    }
+    samples
+}
+
+fn write_wav(samples: &[i16], path: &str) -> std::io::Result<()> {
+    let mut out = BufWriter::new(fs::File::create(path)?);
+    out.write_all(&render::wav_header(SAMPLE_RATE, samples.len() as u32))?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (name, output, waveform) = match &args[..] {
+        [name, output] => (name, output, None),
+        [name, output, flag, wave] if flag == "--wave" && waveform(wave).is_some() => {
+            (name, output, waveform(wave))
+        }
+        _ => {
+            eprintln!("usage: render <tune> <out.wav | --timeline> [--wave <waveform>]");
+            let names: Vec<_> = LIBRARY.iter().map(track_name).collect();
+            eprintln!("tunes: {} or an RTTTL file", names.join(", "));
+            eprintln!("waveforms: sine, triangle, sawtooth, square");
+            process::exit(2);
+        }
+    };
+    let tune = tune(name).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    });
+
+    let timeline = Timeline::new(tune.clone());
+    if output == "--timeline" {
+        print_timeline(timeline);
+        return;
+    }
+    let note_count = Timeline::new(tune.clone()).count();
+    let samples = match waveform {
+        Some(waveform) => synth_samples(tune, waveform),
+        None => buzzer_samples(timeline),
+    };
+    if let Err(err) = write_wav(&samples, output) {
+        eprintln!("{output}: {err}");
+        process::exit(1);
+    }
+    println!(
+        "{output}: {note_count} notes, {:.1} s",
+        samples.len() as f64 / f64::from(SAMPLE_RATE)
+    );
+}
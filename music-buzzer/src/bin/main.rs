@@ -5,14 +5,72 @@
     reason = "mem::forget is generally not safe to do with esp_hal types, especially those \
     holding buffers for the duration of a data transfer."
 )]
-use esp_hal::ledc::channel::ChannelIFace;
-use esp_hal::ledc::timer::TimerIFace;
-use music_buzzer::music::{self, Song};
+#[cfg(all(feature = "dac", feature = "i2s"))]
+compile_error!("the `dac` and `i2s` features can't be enabled together");
+#[cfg(all(feature = "synth", not(any(feature = "dac", feature = "i2s"))))]
+compile_error!("the `synth` feature is enabled by `dac` or `i2s`, enable one of them instead");
+
+#[cfg(feature = "dac")]
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "dac")]
+use critical_section::Mutex;
+use defmt::info;
+use embassy_executor::Spawner;
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::Timer;
+#[cfg(not(feature = "synth"))]
+use embassy_time::{with_deadline, Duration, Instant};
+#[cfg(feature = "dac")]
+use esp_hal::analog::dac::Dac;
 use esp_hal::clock::CpuClock;
-use esp_hal::ledc::{channel, timer, HighSpeed, Ledc, LowSpeed};
-use esp_hal::main;
-use esp_hal::time::{Duration, Instant, Rate};
-use music_buzzer::pink_panther;
+#[cfg(feature = "i2s")]
+use esp_hal::dma_circular_buffers;
+use esp_hal::gpio::{Input, InputConfig, Pull};
+#[cfg(feature = "dac")]
+use esp_hal::handler;
+#[cfg(feature = "i2s")]
+use esp_hal::i2s::master::{DataFormat, I2s, Standard};
+#[cfg(not(feature = "synth"))]
+use esp_hal::ledc::{
+    channel::{self, ChannelHW, ChannelIFace},
+    timer::{self, TimerIFace},
+    HighSpeed, Ledc,
+};
+#[cfg(feature = "dac")]
+use esp_hal::peripherals::{DAC1, GPIO25, TIMG0};
+#[cfg(feature = "i2s")]
+use esp_hal::peripherals::{DMA_I2S0, GPIO14, GPIO22, GPIO27, I2S0};
+#[cfg(not(feature = "synth"))]
+use esp_hal::peripherals::{GPIO32, GPIO33, LEDC};
+use esp_hal::rng::Rng;
+use esp_hal::time::Rate;
+use esp_hal::timer::timg::TimerGroup;
+#[cfg(feature = "dac")]
+use esp_hal::timer::PeriodicTimer;
+#[cfg(feature = "dac")]
+use esp_hal::Blocking;
+use esp_println as _;
+#[cfg(feature = "dac")]
+use heapless::Deque;
+use music_buzzer::library::{LIBRARY, TRACKS};
+use music_buzzer::now_playing::NowPlaying;
+use music_buzzer::player::{Command, Player, Status};
+#[cfg(not(feature = "synth"))]
+use music_buzzer::player::{Note, PARTS};
+use music_buzzer::playlist::Playlist;
+#[cfg(feature = "dac")]
+use music_buzzer::synth::dac_level;
+#[cfg(feature = "synth")]
+use music_buzzer::synth::{Synth, Waveform};
+#[cfg(not(feature = "synth"))]
+use music_buzzer::voice::STEP_MS;
+use ssd1306::{
+    mode::DisplayConfigAsync, prelude::DisplayRotation, size::DisplaySize128x64,
+    I2CDisplayInterface, Ssd1306Async,
+};
 #[panic_handler]
 fn panic(_: &core::panic::PanicInfo) -> ! {
     loop {}
@@ -20,73 +78,514 @@ fn panic(_: &core::panic::PanicInfo) -> ! {
 // This creates a default app-descriptor required by the esp-idf bootloader.
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
-// Helper function
-fn blocking_delay(duration: Duration) {
-    let delay_start = Instant::now();
-    while delay_start.elapsed() < duration {}
+
+// Any task can drive the buzzer through `player_task`, e.g. a web server
+static PLAYER_COMMANDS: Channel<CriticalSectionRawMutex, Command, 4> = Channel::new();
+// Statuses tagged with the number of `Command::Play` handled before them, so that
+// the ones of a tune already replaced can be told apart
+static PLAYER_STATUS: Channel<CriticalSectionRawMutex, (u32, Status), 8> = Channel::new();
+// `Command::Play` handled by `player_task`
+static PLAYS: AtomicU32 = AtomicU32::new(0);
+
+// Presses of the playlist buttons, sent by `button_task`
+static BUTTONS: Channel<CriticalSectionRawMutex, Button, 4> = Channel::new();
+
+// Percent of the full volume the tunes are played at
+const VOLUME: u8 = 80;
+
+// Time the contacts of a button bounce for after a press
+const DEBOUNCE_MS: u64 = 30;
+
+// Samples per second of the synthesizer, and its voice. The DAC is written a
+// sample at a time by an interrupt, at a lower rate.
+#[cfg(feature = "dac")]
+const SAMPLE_RATE: u32 = 8_000;
+#[cfg(feature = "i2s")]
+const SAMPLE_RATE: u32 = 16_000;
+#[cfg(feature = "synth")]
+const WAVEFORM: Waveform = Waveform::Triangle;
+// Bytes of a 16-bit stereo frame, the same sample goes to both channels
+#[cfg(feature = "i2s")]
+const FRAME_BYTES: usize = 4;
+// A tenth of a second of frames, commands are heard that late at most
+#[cfg(feature = "i2s")]
+const DMA_BUFFER_BYTES: usize = SAMPLE_RATE as usize / 10 * FRAME_BYTES;
+// An eighth of a second of DAC levels, commands are heard that late at most
+#[cfg(feature = "dac")]
+const DAC_QUEUE_LEN: usize = 1024;
+// Samples synthesized at once
+#[cfg(feature = "synth")]
+const CHUNK: usize = 256;
+
+// The DAC and the timer setting its pace, for `write_dac`
+#[cfg(feature = "dac")]
+struct DacOutput {
+    dac: Dac<'static, DAC1<'static>>,
+    timer: PeriodicTimer<'static, Blocking>,
 }
-#[main]
-fn main() -> ! {
+#[cfg(feature = "dac")]
+static DAC_OUTPUT: Mutex<RefCell<Option<DacOutput>>> = Mutex::new(RefCell::new(None));
+// Levels waiting for the DAC, queued by `player_task`
+#[cfg(feature = "dac")]
+static DAC_QUEUE: Mutex<RefCell<Deque<u8, DAC_QUEUE_LEN>>> = Mutex::new(RefCell::new(Deque::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Button {
+    Previous,
+    Next,
+    Shuffle,
+}
+
+#[esp_hal_embassy::main]
+async fn main(spawner: Spawner) {
     // generator version: 0.4.0
     let config = esp_hal::Config::default().with_cpu_clock(CpuClock::_80MHz);
     let peripherals = esp_hal::init(config);
-    
-    let buzzer_pin = peripherals.GPIO33;  // Remove mutability since we're moving ownership
-    let ledc = Ledc::new(peripherals.LEDC);
-    let song = Song::new(pink_panther::TEMPO);
-    
-    
-    // Initialize LEDC timer once
-    let mut hstimer0 = ledc.timer::<HighSpeed>(timer::Number::Timer0);
-    hstimer0
-        .configure(timer::config::Config {
-            duty: timer::config::Duty::Duty10Bit,
-            clock_source: timer::HSClockSource::APBClk,
-            frequency: Rate::from_hz(440), // Default frequency
-        })
+
+    let timer0 = TimerGroup::new(peripherals.TIMG1);
+    esp_hal_embassy::init(timer0.timer0);
+
+    #[cfg(not(feature = "synth"))]
+    spawner
+        .spawn(player_task(
+            peripherals.LEDC,
+            peripherals.GPIO33,
+            peripherals.GPIO32,
+        ))
         .unwrap();
-    
-    // Initialize LEDC channel once (move buzzer here)
-    let mut channel0 = ledc.channel(channel::Number::Channel0, buzzer_pin);
-    channel0
-        .configure(channel::config::Config {
-            timer: &hstimer0,
-            duty_pct: 0,  // Start with 0% duty (silent)
-            pin_config: channel::config::PinConfig::PushPull,
-        })
+    #[cfg(feature = "dac")]
+    spawner
+        .spawn(player_task(
+            peripherals.DAC1,
+            peripherals.GPIO25,
+            peripherals.TIMG0,
+        ))
         .unwrap();
-    
-    for (note, duration_type) in pink_panther::MELODY {
-        let note_duration = song.calc_note_duration(duration_type) as u64;
-        let pause_duration = note_duration / 10; // 10% of note_duration
-        
-        if note == music::REST {
-            channel0.set_duty(0).unwrap();  // Ensure silence
-            blocking_delay(Duration::from_millis(note_duration));
-            continue;
+    #[cfg(feature = "i2s")]
+    spawner
+        .spawn(player_task(
+            peripherals.I2S0,
+            peripherals.DMA_I2S0,
+            peripherals.GPIO27,
+            peripherals.GPIO14,
+            peripherals.GPIO22,
+        ))
+        .unwrap();
+    PLAYER_COMMANDS.send(Command::SetVolume(VOLUME)).await;
+
+    let pull_up = InputConfig::default().with_pull(Pull::Up);
+    let buttons = [
+        (Input::new(peripherals.GPIO4, pull_up), Button::Previous),
+        (Input::new(peripherals.GPIO5, pull_up), Button::Next),
+        (Input::new(peripherals.GPIO19, pull_up), Button::Shuffle),
+    ];
+    for (input, button) in buttons {
+        spawner.spawn(button_task(input, button)).unwrap();
+    }
+
+    let i2c_bus = esp_hal::i2c::master::I2c::new(
+        peripherals.I2C0,
+        esp_hal::i2c::master::Config::default().with_frequency(Rate::from_khz(400)),
+    )
+    .unwrap()
+    .with_scl(peripherals.GPIO18)
+    .with_sda(peripherals.GPIO23)
+    .into_async();
+    let interface = I2CDisplayInterface::new(i2c_bus);
+    let mut display = Ssd1306Async::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode();
+    display.init().await.unwrap();
+
+    // Shuffles with the hardware generator
+    let mut rng = Rng::new(peripherals.RNG);
+    let mut playlist = Playlist::<TRACKS>::new();
+    // `Command::Play` sent so far, to match the tags of the statuses
+    let mut plays = 0;
+    let mut now_playing = play(&playlist, &mut plays).await;
+
+    loop {
+        now_playing.draw(&mut display).unwrap();
+        display.flush().await.unwrap();
+
+        match select(PLAYER_STATUS.receive(), BUTTONS.receive()).await {
+            Either::First((tune, status)) => {
+                log_status(status);
+                // Left over from a track replaced by Previous or Next. A newer
+                // tune is one another task played, it's followed from then on.
+                if tune < plays {
+                    continue;
+                }
+                plays = tune;
+                now_playing.update(status);
+                if status == Status::Finished {
+                    playlist.skip_forward();
+                    now_playing = play(&playlist, &mut plays).await;
+                }
+            }
+            Either::Second(Button::Previous) => {
+                playlist.skip_back();
+                now_playing = play(&playlist, &mut plays).await;
+            }
+            Either::Second(Button::Next) => {
+                playlist.skip_forward();
+                now_playing = play(&playlist, &mut plays).await;
+            }
+            Either::Second(Button::Shuffle) => {
+                let shuffled = playlist.toggle_shuffle(|| rng.random());
+                info!("Shuffle {}", shuffled);
+                now_playing.shuffled = shuffled;
+                now_playing.track = playlist.position() + 1;
+            }
         }
-        
-        // Update frequency for this note
-        let freq = Rate::from_hz(note as u32);
-        let mut hstimer0 = ledc.timer::<HighSpeed>(timer::Number::Timer0);
-        hstimer0
-            .configure(timer::config::Config {
-                duty: timer::config::Duty::Duty10Bit,
-                clock_source: timer::HSClockSource::APBClk,
-                frequency: freq, //notes
+    }
+}
+
+// Starts the current track of the playlist, counting it in `plays`. Returns what
+// the screen shows for it.
+async fn play(playlist: &Playlist<TRACKS>, plays: &mut u32) -> NowPlaying {
+    let track = &LIBRARY[playlist.current()];
+    info!("Playing {}", track.title);
+    PLAYER_COMMANDS.send(Command::Play(track.tune())).await;
+    *plays += 1;
+    NowPlaying::new(
+        track.title,
+        playlist.position() + 1,
+        TRACKS,
+        playlist.is_shuffled(),
+    )
+}
+
+// Sends a press of `input` to `BUTTONS`, the buttons pull the pins low
+#[embassy_executor::task(pool_size = 3)]
+async fn button_task(mut input: Input<'static>, button: Button) {
+    loop {
+        input.wait_for_falling_edge().await;
+        BUTTONS.send(button).await;
+        Timer::after_millis(DEBOUNCE_MS).await;
+        input.wait_for_high().await;
+        Timer::after_millis(DEBOUNCE_MS).await;
+    }
+}
+
+fn log_status(status: Status) {
+    match status {
+        Status::Started { notes, .. } => info!("Playing {} notes", notes),
+        Status::Progress { played, notes, .. } => info!("Note {}/{}", played, notes),
+        Status::Paused => info!("Paused"),
+        Status::Resumed => info!("Resumed"),
+        Status::Stopped => info!("Stopped"),
+        Status::Finished => info!("Finished"),
+        Status::TempoChanged(tempo) => info!("Tempo {} bpm", tempo),
+        Status::VolumeChanged(volume) => info!("Volume {}%", volume),
+    }
+}
+
+#[cfg(not(feature = "synth"))]
+// Timers of the buzzers, one for each part to play its own frequency
+const TIMERS: [timer::Number; PARTS] = [timer::Number::Timer0, timer::Number::Timer1];
+
+#[cfg(not(feature = "synth"))]
+// Owns the buzzers, plays the tunes sent to `PLAYER_COMMANDS` and reports on
+// `PLAYER_STATUS`. The melody plays on the first buzzer, the bass line of a
+// duet on the second one.
+#[embassy_executor::task]
+async fn player_task(ledc: LEDC<'static>, melody_pin: GPIO33<'static>, bass_pin: GPIO32<'static>) {
+    let ledc = Ledc::new(ledc);
+    let timers = TIMERS.map(|number| {
+        let mut timer = ledc.timer::<HighSpeed>(number);
+        timer.configure(tone_timer_config(440)).unwrap();
+        timer
+    });
+
+    let mut channels = [
+        ledc.channel(channel::Number::Channel0, melody_pin),
+        ledc.channel(channel::Number::Channel1, bass_pin),
+    ];
+    for (channel, timer) in channels.iter_mut().zip(&timers) {
+        channel
+            .configure(channel::config::Config {
+                timer,
+                duty_pct: 0, // Silent until the first note
+                pin_config: channel::config::PinConfig::PushPull,
             })
             .unwrap();
-        
-        // Set duty to 50% to play the note
-        channel0.set_duty(50).unwrap();
-        blocking_delay(Duration::from_millis(note_duration - pause_duration));
-        
-        // Brief pause between notes
-        channel0.set_duty(0).unwrap();
-        blocking_delay(Duration::from_millis(pause_duration));
     }
-    
+
+    let mut player = Player::new();
+    // Frequencies the timers are tuned to, they are only retuned when it changes
+    let mut tuned = [None; PARTS];
+    loop {
+        let Some(change_ms) = player.next_change_ms() else {
+            silence(&channels);
+            let command = PLAYER_COMMANDS.receive().await;
+            count_play(&command);
+            report(player.handle(command)).await;
+            continue;
+        };
+
+        let notes = player.notes();
+        for (part, note) in notes.iter().enumerate() {
+            let Some(note) = note.filter(|note| !note.is_rest()) else {
+                continue;
+            };
+            let frequency = note.frequency as u32;
+            if tuned[part] != Some(frequency) {
+                // The channel holds on to the timer, retune the same hardware timer
+                let mut retune = ledc.timer::<HighSpeed>(TIMERS[part]);
+                retune.configure(tone_timer_config(frequency)).unwrap();
+                tuned[part] = Some(frequency);
+            }
+        }
+        if play_until_change(change_ms, &notes, &mut player, &channels).await {
+            report(player.advance()).await;
+        } else {
+            silence(&channels);
+        }
+    }
+}
+
+#[cfg(not(feature = "synth"))]
+// Steps the duty of the channels through the envelopes of their notes, and
+// through their release in the silence after them, until the next change.
+// Returns `false` when a command cut it short.
+async fn play_until_change(
+    change_ms: u32,
+    notes: &[Option<Note>; PARTS],
+    player: &mut Player,
+    channels: &[channel::Channel<'_, HighSpeed>; PARTS],
+) -> bool {
+    let start = Instant::now();
+    let change = start + Duration::from_millis(change_ms.into());
+    // The notes of a duet can have started at an earlier change
+    let elapsed: [Option<u32>; PARTS] = core::array::from_fn(|part| player.elapsed_ms(part));
+    let mut since_ms = 0;
+    let mut played = true;
+    while played && since_ms < change_ms {
+        let mut sounding = false;
+        for part in 0..PARTS {
+            let duty = match (notes[part], elapsed[part]) {
+                (Some(note), Some(elapsed_ms)) if !note.is_rest() => {
+                    let elapsed_ms = elapsed_ms + since_ms;
+                    // Read on every step, a new volume applies to the notes playing
+                    let duty = player.voice().duty(elapsed_ms, note.on_ms);
+                    sounding |= duty > 0 || elapsed_ms < note.on_ms;
+                    duty
+                }
+                _ => 0,
+            };
+            channels[part].set_duty_hw(duty);
+        }
+        // Nothing to step once all the parts are silent for good
+        let step_end = if sounding {
+            (start + Duration::from_millis((since_ms + STEP_MS).into())).min(change)
+        } else {
+            change
+        };
+        played = handle_commands_until(step_end, player).await;
+        since_ms = (step_end - start).as_millis() as u32;
+    }
+    played
+}
+
+#[cfg(not(feature = "synth"))]
+fn silence(channels: &[channel::Channel<'_, HighSpeed>; PARTS]) {
+    for channel in channels {
+        channel.set_duty_hw(0);
+    }
+}
+
+#[cfg(not(feature = "synth"))]
+// Handles the commands sent while a note plays. Returns `false` when one of them
+// cut the note short.
+async fn handle_commands_until(deadline: Instant, player: &mut Player) -> bool {
+    while let Ok(command) = with_deadline(deadline, PLAYER_COMMANDS.receive()).await {
+        let interrupts = command.interrupts_note();
+        count_play(&command);
+        report(player.handle(command)).await;
+        if interrupts {
+            return false;
+        }
+    }
+    true
+}
+
+// Counts the tunes started, for the tags of the statuses
+fn count_play(command: &Command) {
+    if matches!(command, Command::Play(_)) {
+        PLAYS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+async fn report(status: Option<Status>) {
+    let Some(status) = status else {
+        return;
+    };
+    if is_final(status) {
+        // The playlist moves on with them, and there is nothing left to play
+        PLAYER_STATUS.send(tagged(status)).await;
+    } else {
+        // Dropped when nobody reads them, a note never waits for a listener
+        PLAYER_STATUS.try_send(tagged(status)).ok();
+    }
+}
+
+fn tagged(status: Status) -> (u32, Status) {
+    (PLAYS.load(Ordering::Relaxed), status)
+}
+
+// Statuses after which the tune plays no more
+fn is_final(status: Status) -> bool {
+    matches!(status, Status::Finished | Status::Stopped)
+}
+
+// Hands the commands waiting to the synth, then fills `chunk` with what plays
+// next. The end of the tune is returned rather than reported, for the caller to
+// report once the chunk is out.
+#[cfg(feature = "synth")]
+async fn synthesize(synth: &mut Synth, player: &mut Player, chunk: &mut [i16]) -> Option<Status> {
+    while let Ok(command) = PLAYER_COMMANDS.try_receive() {
+        count_play(&command);
+        report(synth.handle(player, command)).await;
+    }
+    let mut ended = None;
+    synth.fill(player, chunk, |status| {
+        if is_final(status) {
+            ended = Some(status);
+        } else {
+            PLAYER_STATUS.try_send(tagged(status)).ok();
+        }
+    });
+    ended
+}
+
+// Plays on a speaker rather than the buzzers, through the 8-bit DAC of the ESP32
+// on GPIO25: the `Synth` queues its samples as DAC levels, and the interrupt of a
+// TIMG0 timer writes them one by one at `SAMPLE_RATE`. The melody and the bass
+// line of a duet are mixed.
+#[cfg(feature = "dac")]
+#[embassy_executor::task]
+async fn player_task(dac: DAC1<'static>, dac_pin: GPIO25<'static>, timg: TIMG0<'static>) {
+    let mut timer = PeriodicTimer::new(TimerGroup::new(timg).timer0);
+    timer.set_interrupt_handler(write_dac);
+    timer.listen();
+    let mut output = DacOutput {
+        dac: Dac::new(dac, dac_pin),
+        timer,
+    };
+    output.dac.write(dac_level(0));
+    critical_section::with(|cs| {
+        // The interrupt waits for the end of the critical section, the output
+        // is in place by then
+        output
+            .timer
+            .start(esp_hal::time::Duration::from_micros(
+                1_000_000 / u64::from(SAMPLE_RATE),
+            ))
+            .unwrap();
+        DAC_OUTPUT.borrow(cs).replace(Some(output));
+    });
+
+    let mut synth = Synth::new(SAMPLE_RATE, WAVEFORM);
+    let mut player = Player::new();
+    let mut samples = [0; CHUNK];
+    loop {
+        let free = critical_section::with(|cs| {
+            let queue = DAC_QUEUE.borrow_ref(cs);
+            queue.capacity() - queue.len()
+        });
+        if free < CHUNK {
+            // Until a chunk is played
+            Timer::after_micros(CHUNK as u64 * 1_000_000 / u64::from(SAMPLE_RATE)).await;
+            continue;
+        }
+        // Silence is queued too while idle, so that the DAC stays at mid-level
+        let ended = synthesize(&mut synth, &mut player, &mut samples).await;
+        critical_section::with(|cs| {
+            let mut queue = DAC_QUEUE.borrow_ref_mut(cs);
+            for &sample in &samples {
+                queue.push_back(dac_level(sample)).ok();
+            }
+        });
+        report(ended).await;
+    }
+}
+
+// Writes the next level queued to the DAC, at each period of the timer
+#[cfg(feature = "dac")]
+#[handler]
+fn write_dac() {
+    critical_section::with(|cs| {
+        let mut output = DAC_OUTPUT.borrow_ref_mut(cs);
+        let Some(output) = output.as_mut() else {
+            return;
+        };
+        output.timer.clear_interrupt();
+        // Silence when `player_task` falls behind
+        let level = DAC_QUEUE
+            .borrow_ref_mut(cs)
+            .pop_front()
+            .unwrap_or(dac_level(0));
+        output.dac.write(level);
+    });
+}
+
+// Plays on a speaker rather than the buzzers, through an external I2S DAC or
+// amplifier module like the MAX98357A: the `Synth` fills the DMA buffer of I2S0
+// as it empties. The melody and the bass line of a duet are mixed. The clocks
+// leave GPIO25 and GPIO26 free, they are the pins of the built-in DACs.
+#[cfg(feature = "i2s")]
+#[embassy_executor::task]
+async fn player_task(
+    i2s: I2S0<'static>,
+    dma: DMA_I2S0<'static>,
+    bclk_pin: GPIO27<'static>,
+    ws_pin: GPIO14<'static>,
+    dout_pin: GPIO22<'static>,
+) {
+    let (_, _, tx_buffer, tx_descriptors) = dma_circular_buffers!(0, DMA_BUFFER_BYTES);
+    let i2s = I2s::new(
+        i2s,
+        Standard::Philips,
+        DataFormat::Data16Channel16,
+        Rate::from_hz(SAMPLE_RATE),
+        dma,
+    )
+    .into_async();
+    let i2s_tx = i2s
+        .i2s_tx
+        .with_bclk(bclk_pin)
+        .with_ws(ws_pin)
+        .with_dout(dout_pin)
+        .build(tx_descriptors);
+    tx_buffer.fill(0);
+    let mut transfer = i2s_tx.write_dma_circular_async(tx_buffer).unwrap();
+
+    let mut synth = Synth::new(SAMPLE_RATE, WAVEFORM);
+    let mut player = Player::new();
+    let mut samples = [0; CHUNK];
+    let mut frames = [0; CHUNK * FRAME_BYTES];
     loop {
-        blocking_delay(Duration::from_millis(5));
+        // Silence is pushed too while idle, the DMA would loop over the last samples
+        let available = transfer.available().await.unwrap() / FRAME_BYTES;
+        let chunk = &mut samples[..available.min(CHUNK)];
+        let ended = synthesize(&mut synth, &mut player, chunk).await;
+        for (frame, sample) in frames.chunks_exact_mut(FRAME_BYTES).zip(chunk.iter()) {
+            let [low, high] = sample.to_le_bytes();
+            frame.copy_from_slice(&[low, high, low, high]);
+        }
+        transfer
+            .push(&frames[..chunk.len() * FRAME_BYTES])
+            .await
+            .unwrap();
+        report(ended).await;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "synth"))]
+fn tone_timer_config(freq_hz: u32) -> timer::config::Config<timer::HSClockSource> {
+    timer::config::Config {
+        duty: timer::config::Duty::Duty10Bit,
+        clock_source: timer::HSClockSource::APBClk,
+        frequency: Rate::from_hz(freq_hz),
+    }
+}
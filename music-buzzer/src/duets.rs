@@ -0,0 +1,57 @@
+//! Tunes with a bass line, for the two buzzers.
+
+use crate::duration::Duration;
+use crate::pitch::{Pitch, PitchClass::*};
+use crate::score::{Duet, Score};
+
+pub const TWINKLE_TEMPO: u16 = 90;
+
+/// The first line of Twinkle Twinkle, over a bass line in half notes.
+pub static TWINKLE: Duet<14, 8> = Duet::new(
+    Score::new(TWINKLE_TEMPO)
+        .note(Pitch::new(C, 5), Duration::QUARTER)
+        .note(Pitch::new(C, 5), Duration::QUARTER)
+        .note(Pitch::new(G, 5), Duration::QUARTER)
+        .note(Pitch::new(G, 5), Duration::QUARTER)
+        .note(Pitch::new(A, 5), Duration::QUARTER)
+        .note(Pitch::new(A, 5), Duration::QUARTER)
+        .note(Pitch::new(G, 5), Duration::HALF)
+        .note(Pitch::new(F, 5), Duration::QUARTER)
+        .note(Pitch::new(F, 5), Duration::QUARTER)
+        .note(Pitch::new(E, 5), Duration::QUARTER)
+        .note(Pitch::new(E, 5), Duration::QUARTER)
+        .note(Pitch::new(D, 5), Duration::QUARTER)
+        .note(Pitch::new(D, 5), Duration::QUARTER)
+        .note(Pitch::new(C, 5), Duration::HALF),
+    Score::new(TWINKLE_TEMPO)
+        .note(Pitch::new(C, 3), Duration::HALF)
+        .note(Pitch::new(E, 3), Duration::HALF)
+        .note(Pitch::new(F, 3), Duration::HALF)
+        .note(Pitch::new(E, 3), Duration::HALF)
+        .note(Pitch::new(D, 3), Duration::HALF)
+        .note(Pitch::new(C, 3), Duration::HALF)
+        .note(Pitch::new(G, 2), Duration::HALF)
+        .note(Pitch::new(C, 3), Duration::HALF),
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::score::Event;
+
+    fn length(events: &[Event]) -> Duration {
+        events
+            .iter()
+            .fold(Duration::ZERO, |length, event| match *event {
+                Event::Note(_, duration) | Event::Rest(duration) => length + duration,
+                Event::Tempo(_) => length,
+            })
+    }
+
+    #[test]
+    fn parts_end_together() {
+        let length_of_melody = length(TWINKLE.melody.events());
+        assert_eq!(length_of_melody, Duration::new(4, 1));
+        assert_eq!(length(TWINKLE.bass.events()), length_of_melody);
+    }
+}
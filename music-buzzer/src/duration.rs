@@ -0,0 +1,166 @@
+//! Typed note lengths, instead of the dividers of `Song::calc_note_duration`.
+
+use core::cmp::Ordering;
+use core::ops::{Add, Sub};
+
+/// Length of a note as a fraction of a whole note, e.g. 3/8 for a dotted quarter.
+///
+/// Tempos are in quarter notes per minute, like the `TEMPO` of the melodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Duration {
+    // In lowest terms, so that equal lengths compare equal
+    numerator: u32,
+    denominator: u32,
+}
+
+impl Duration {
+    /// No time at all, the position of the start of a tune.
+    pub const ZERO: Self = Self::new(0, 1);
+    pub const WHOLE: Self = Self::new(1, 1);
+    pub const HALF: Self = Self::new(1, 2);
+    pub const QUARTER: Self = Self::new(1, 4);
+    pub const EIGHTH: Self = Self::new(1, 8);
+    pub const SIXTEENTH: Self = Self::new(1, 16);
+    pub const THIRTY_SECOND: Self = Self::new(1, 32);
+
+    pub const fn new(numerator: u32, denominator: u32) -> Self {
+        assert!(denominator > 0, "a duration needs a denominator");
+        let divisor = gcd(numerator, denominator);
+        Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    /// Duration of a divider of the melody tables, a negative one being dotted.
+    pub const fn from_divider(divider: i16) -> Self {
+        let plain = Self::new(1, divider.unsigned_abs() as u32);
+        if divider < 0 {
+            plain.dotted()
+        } else {
+            plain
+        }
+    }
+
+    /// Half as long again.
+    pub const fn dotted(self) -> Self {
+        Self::new(self.numerator * 3, self.denominator * 2)
+    }
+
+    /// One of three notes played in the time of two.
+    pub const fn triplet(self) -> Self {
+        Self::new(self.numerator * 2, self.denominator * 3)
+    }
+
+    /// The two lengths held as one note.
+    pub const fn tie(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+
+    /// `(numerator, denominator)` of the fraction of a whole note.
+    pub const fn fraction(self) -> (u32, u32) {
+        (self.numerator, self.denominator)
+    }
+
+    /// Length in milliseconds at `tempo` quarter notes per minute.
+    pub const fn millis(self, tempo: u16) -> u32 {
+        let whole_note_ms = 60_000 * 4;
+        (whole_note_ms * self.numerator as u64 / (self.denominator as u64 * tempo as u64)) as u32
+    }
+}
+
+impl Default for Duration {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Ord for Duration {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let left = u64::from(self.numerator) * u64::from(other.denominator);
+        let right = u64::from(other.numerator) * u64::from(self.denominator);
+        left.cmp(&right)
+    }
+}
+
+impl PartialOrd for Duration {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Duration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.tie(other)
+    }
+}
+
+/// Time between two positions of a tune, `other` must not be after `self`.
+impl Sub for Duration {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.denominator - other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a == 0 {
+        1
+    } else {
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::Song;
+
+    #[test]
+    fn dividers_keep_their_length() {
+        let song = Song::new(120);
+        for divider in [1, 2, 4, 8, 16, 32, -2, -4, -8] {
+            assert_eq!(
+                Duration::from_divider(divider).millis(120),
+                song.calc_note_duration(divider),
+                "divider {divider}"
+            );
+        }
+        assert_eq!(Duration::from_divider(-4), Duration::QUARTER.dotted());
+        assert_eq!(Duration::from_divider(3), Duration::HALF.triplet());
+    }
+
+    #[test]
+    fn triplets_and_ties_add_up() {
+        let triplet = Duration::EIGHTH.triplet();
+        assert_eq!(triplet + triplet + triplet, Duration::QUARTER);
+        assert_eq!(
+            Duration::HALF.tie(Duration::QUARTER),
+            Duration::HALF.dotted()
+        );
+        assert_eq!(Duration::new(6, 16).fraction(), (3, 8));
+        assert_eq!(Duration::QUARTER.millis(60), 1000);
+    }
+
+    #[test]
+    fn positions_compare_and_subtract() {
+        let position = Duration::HALF + Duration::EIGHTH.triplet();
+        assert!(position > Duration::HALF);
+        assert!(position < Duration::HALF.dotted());
+        assert_eq!(position - Duration::HALF, Duration::new(1, 12));
+        assert_eq!(Duration::default(), Duration::ZERO);
+        assert_eq!(Duration::QUARTER - Duration::QUARTER, Duration::ZERO);
+    }
+}
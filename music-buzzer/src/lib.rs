@@ -1,3 +1,25 @@
-#![no_std]
+//! Tunes, players and display of the buzzer firmware.
+//!
+//! Nothing here depends on the HAL, the unit tests run on the host. `--lib` leaves
+//! out `tests/hello_test.rs`, which runs on the board with `embedded-test`:
+//!
+//! ```text
+//! cargo +stable test --lib --target x86_64-unknown-linux-gnu
+//! ```
+#![cfg_attr(not(test), no_std)]
+pub mod duets;
+pub mod duration;
+pub mod library;
 pub mod music;
-pub mod pink_panther;
\ No newline at end of file
+pub mod now_playing;
+pub mod pink_panther;
+pub mod pitch;
+pub mod player;
+pub mod playlist;
+pub mod render;
+pub mod ringtones;
+pub mod rtttl;
+pub mod score;
+pub mod songs;
+pub mod synth;
+pub mod voice;
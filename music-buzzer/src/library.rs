@@ -0,0 +1,85 @@
+//! The songs the firmware knows, by title.
+
+use crate::player::Tune;
+use crate::rtttl::Ringtone;
+use crate::{duets, pink_panther, ringtones, songs};
+
+pub struct Track {
+    pub title: &'static str,
+    // Builds the tune when the track is played, ringtones can't be parsed in a const
+    melody: fn() -> Tune,
+}
+
+impl Track {
+    pub fn tune(&self) -> Tune {
+        (self.melody)()
+    }
+
+    /// Tempo the track starts at, in quarter notes per minute, the `b` of the
+    /// ringtones.
+    pub fn tempo(&self) -> u16 {
+        self.tune().tempo()
+    }
+}
+
+// Checked on the host by the library tests
+fn ringtone(text: &'static str) -> Tune {
+    Tune::Ringtone(Ringtone::parse(text).unwrap())
+}
+
+pub const TRACKS: usize = 6;
+
+pub static LIBRARY: [Track; TRACKS] = [
+    Track {
+        title: "Pink Panther",
+        melody: || Tune::from(&pink_panther::SCORE),
+    },
+    Track {
+        title: "Nokia Tune",
+        melody: || ringtone(ringtones::NOKIA),
+    },
+    Track {
+        title: "Tetris",
+        melody: || ringtone(ringtones::TETRIS),
+    },
+    Track {
+        title: "Ode to Joy",
+        melody: || Tune::Melody {
+            notes: &songs::ode_to_joy::MELODY,
+            tempo: songs::ode_to_joy::TEMPO,
+        },
+    },
+    Track {
+        title: "Twinkle Twinkle",
+        melody: || Tune::Melody {
+            notes: &songs::twinkle::MELODY,
+            tempo: songs::twinkle::TEMPO,
+        },
+    },
+    Track {
+        title: "Twinkle Duet",
+        melody: || Tune::from(&duets::TWINKLE),
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_play_at_their_tempo() {
+        let header_tempo = |text| Ringtone::parse(text).unwrap().tempo;
+        let tempos = LIBRARY.each_ref().map(Track::tempo);
+        assert_eq!(
+            tempos,
+            [
+                pink_panther::TEMPO,
+                header_tempo(ringtones::NOKIA),
+                header_tempo(ringtones::TETRIS),
+                songs::ode_to_joy::TEMPO,
+                songs::twinkle::TEMPO,
+                duets::TWINKLE_TEMPO
+            ]
+        );
+    }
+}
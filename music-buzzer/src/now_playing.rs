@@ -0,0 +1,230 @@
+//! Now-playing screen for the 128x64 OLED display.
+//!
+//! ```text
+//! 2/5           SHUFFLE
+//! Nokia Tune
+//!
+//!          C#6
+//!
+//! [#######           ]
+//! ```
+
+use crate::pitch::Pitch;
+use crate::player::Status;
+use core::fmt::Write;
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use heapless::String;
+
+const TITLE_Y: i32 = 12;
+const NOTE_Y: i32 = 26;
+const PROGRESS_BAR: Rectangle = Rectangle::new(Point::new(4, 52), Size::new(120, 8));
+
+/// What the screen shows, updated from the `Status` of the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NowPlaying {
+    pub title: &'static str,
+    /// Position of the track in the playlist, from 1.
+    pub track: usize,
+    pub tracks: usize,
+    pub shuffled: bool,
+    played: usize,
+    notes: usize,
+    pitch: Option<Pitch>,
+    paused: bool,
+}
+
+impl NowPlaying {
+    pub fn new(title: &'static str, track: usize, tracks: usize, shuffled: bool) -> Self {
+        Self {
+            title,
+            track,
+            tracks,
+            shuffled,
+            played: 0,
+            notes: 0,
+            pitch: None,
+            paused: false,
+        }
+    }
+
+    pub fn update(&mut self, status: Status) {
+        match status {
+            Status::Started { notes, pitch } => {
+                (self.played, self.notes, self.pitch) = (0, notes, pitch);
+                self.paused = false;
+            }
+            Status::Progress {
+                played,
+                notes,
+                pitch,
+            } => (self.played, self.notes, self.pitch) = (played, notes, pitch),
+            Status::Paused => self.paused = true,
+            Status::Resumed => self.paused = false,
+            Status::Finished => (self.played, self.pitch) = (self.notes, None),
+            Status::Stopped => (self.played, self.pitch) = (0, None),
+            Status::TempoChanged(_) | Status::VolumeChanged(_) => (),
+        }
+    }
+
+    /// Width of the filled part of the progress bar, inside its border.
+    pub fn progress_width(&self) -> u32 {
+        let inner = PROGRESS_BAR.size.width - 4;
+        match self.notes {
+            0 => 0,
+            notes => (inner as usize * self.played.min(notes) / notes) as u32,
+        }
+    }
+
+    /// Draws the whole screen, the display is cleared first.
+    pub fn draw<D>(&self, display: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        display.clear(BinaryColor::Off)?;
+        let small = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        let large = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        let width = display.bounding_box().size.width as i32;
+
+        let mut position: String<12> = String::new();
+        write!(position, "{}/{}", self.track, self.tracks).ok();
+        Text::with_baseline(&position, Point::zero(), small, Baseline::Top).draw(display)?;
+        if self.shuffled {
+            Text::with_text_style(
+                "SHUFFLE",
+                Point::new(width, 0),
+                small,
+                TextStyleBuilder::new()
+                    .alignment(Alignment::Right)
+                    .baseline(Baseline::Top)
+                    .build(),
+            )
+            .draw(display)?;
+        }
+        Text::with_baseline(self.title, Point::new(0, TITLE_Y), small, Baseline::Top)
+            .draw(display)?;
+
+        let mut note: String<8> = String::new();
+        match (self.paused, self.pitch) {
+            (true, _) => note.push_str("||").ok(),
+            (false, Some(pitch)) => write!(note, "{pitch}").ok(),
+            (false, None) => note.push_str("-").ok(),
+        };
+        Text::with_text_style(
+            &note,
+            Point::new(width / 2, NOTE_Y),
+            large,
+            TextStyleBuilder::new()
+                .alignment(Alignment::Center)
+                .baseline(Baseline::Top)
+                .build(),
+        )
+        .draw(display)?;
+
+        PROGRESS_BAR
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(display)?;
+        let filled = Rectangle::new(
+            PROGRESS_BAR.top_left + Point::new(2, 2),
+            Size::new(self.progress_width(), PROGRESS_BAR.size.height - 4),
+        );
+        filled
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(display)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pitch::PitchClass::*;
+    use core::convert::Infallible;
+
+    // A 128x64 display, lit pixels are `true`
+    struct Screen([[bool; 128]; 64]);
+
+    impl Dimensions for Screen {
+        fn bounding_box(&self) -> Rectangle {
+            Rectangle::new(Point::zero(), Size::new(128, 64))
+        }
+    }
+
+    impl DrawTarget for Screen {
+        type Color = BinaryColor;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if let Some(row) = self.0.get_mut(point.y as usize) {
+                    if let Some(pixel) = row.get_mut(point.x as usize) {
+                        *pixel = color.is_on();
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn lit(screen: &Screen, area: Rectangle) -> usize {
+        area.points()
+            .filter(|point| screen.0[point.y as usize][point.x as usize])
+            .count()
+    }
+
+    #[test]
+    fn follows_the_player() {
+        let mut now_playing = NowPlaying::new("Tetris", 3, 5, false);
+        now_playing.update(Status::Started {
+            notes: 4,
+            pitch: Some(Pitch::new(E, 6)),
+        });
+        assert_eq!(now_playing.progress_width(), 0);
+        now_playing.update(Status::Progress {
+            played: 1,
+            notes: 4,
+            pitch: None,
+        });
+        assert_eq!(now_playing.progress_width(), 29);
+        now_playing.update(Status::Paused);
+        assert!(now_playing.paused);
+        now_playing.update(Status::Finished);
+        assert_eq!(now_playing.progress_width(), 116);
+        assert_eq!(now_playing.pitch, None);
+    }
+
+    #[test]
+    fn draws_the_progress_and_the_note() {
+        let mut now_playing = NowPlaying::new("Ode to Joy", 4, 5, true);
+        now_playing.update(Status::Progress {
+            played: 15,
+            notes: 30,
+            pitch: Some(Pitch::new(Cs, 5)),
+        });
+        let mut screen = Screen([[false; 128]; 64]);
+        now_playing.draw(&mut screen).unwrap();
+
+        // Half of the bar is filled, the other half only has the border
+        let inside = Rectangle::new(Point::new(6, 54), Size::new(116, 4));
+        assert_eq!(lit(&screen, inside), 58 * 4);
+        let note = Rectangle::new(Point::new(0, NOTE_Y), Size::new(128, 20));
+        assert!(lit(&screen, note) > 0);
+        // The shuffle mark on the top right
+        let shuffle = Rectangle::new(Point::new(86, 0), Size::new(42, 10));
+        assert!(lit(&screen, shuffle) > 0);
+
+        now_playing.shuffled = false;
+        now_playing.draw(&mut screen).unwrap();
+        assert_eq!(lit(&screen, shuffle), 0);
+    }
+}
@@ -1,270 +1,268 @@
-use crate::music::*;
+use crate::duration::Duration;
+use crate::pitch::{Pitch, PitchClass::*};
+use crate::score::Score;
 
 pub const TEMPO: u16 = 150;
 
-pub const MELODY: [(f64, i16); 261] = [
-    (REST, 2),//1
-    (REST, 2),//2
-    (REST, 2),//3
-    (REST, 4),//4
-    (NOTE_G7, 8),
-    (NOTE_G7, 8),
-    (NOTE_G7, 4),//
-    (NOTE_E7, 4), 
-    (NOTE_C4, 3), //B
-    (NOTE_G4, 8),
-    (NOTE_G7, 8),//
-    (NOTE_G7, 8),
-    (NOTE_G7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),//
-    (NOTE_F7, 8),
-    (NOTE_G7, 8),
-    (NOTE_G7, 4),//
-    (NOTE_E7, 4),
-    (NOTE_C4, 2), //B
-    (NOTE_E4, 4),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_E7, 8),
-    (NOTE_D7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_D7, 2),
-    (NOTE_C6, 2),
-    (NOTE_B6, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C6, 4),
-    (NOTE_B6, 4),
-    (NOTE_A6, 4),
-    (NOTE_G6, 4),//
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_E7, 8),
-    (NOTE_D7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_D7, 2),
-    (NOTE_C6, 2),
-    (NOTE_B6, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C6, 4),
-    (NOTE_B6, 4),
-    (NOTE_A6, 4),
-    (NOTE_G6, 4),//
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_E7, 8),
-    (NOTE_D7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_D7, 2),
-    (NOTE_C6, 2),
-    (NOTE_B6, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C5, 4),
-    (NOTE_B5, 4),
-    (NOTE_A5, 4),
-    (NOTE_G5, 4),//
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_E7, 8),
-    (NOTE_D7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_D7, 2),
-    (NOTE_C5, 2),
-    (NOTE_B6, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C6, 4),
-    (NOTE_B6, 4),
-    (NOTE_A6, 4),
-    (NOTE_G6, 4),//
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_E7, 8),
-    (NOTE_D7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_D7, 2),
-    (NOTE_C6, 2),
-    (NOTE_B5, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C5, 4),
-    (NOTE_B5, 4),
-    (NOTE_A5, 4),
-    (NOTE_G5, 4),//
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_F7, 4),
-    (NOTE_G7, 4),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_F7, 8),
-    (NOTE_E7, 4),
-    (NOTE_G6, 4),
-    (NOTE_F6, 8),
-    (NOTE_E6, 8),
-    (NOTE_D6, 8),
-    (NOTE_E6, 8),
-    (NOTE_F6, 4),
-    (NOTE_D6, 2),
-    (NOTE_C5, 2),
-    (NOTE_B5, 2),
-    (NOTE_E7, 8),
-    (NOTE_E7, 8),
-    (NOTE_E7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_E7, 4),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_D7, 8),
-    (NOTE_C7, 4),
-    (NOTE_B7, 4),
-    (NOTE_C6, 4),
-    (NOTE_B6, 4),
-    (NOTE_A6, 4),
-    (NOTE_G6, 4),//
-    
-    
-    
-];
+pub static SCORE: Score<261> = Score::new(TEMPO)
+    .rest(Duration::HALF)//1
+    .rest(Duration::HALF)//2
+    .rest(Duration::HALF)//3
+    .rest(Duration::QUARTER)//4
+    .note(Pitch::new(G, 7), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::QUARTER) 
+    .note(Pitch::new(C, 4), Duration::HALF.triplet()) //B
+    .note(Pitch::new(G, 4), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::EIGHTH)//
+    .note(Pitch::new(G, 7), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)//
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::EIGHTH)
+    .note(Pitch::new(G, 7), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 4), Duration::HALF) //B
+    .note(Pitch::new(E, 4), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::HALF)
+    .note(Pitch::new(C, 6), Duration::HALF)
+    .note(Pitch::new(B, 6), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 6), Duration::QUARTER)
+    .note(Pitch::new(B, 6), Duration::QUARTER)
+    .note(Pitch::new(A, 6), Duration::QUARTER)
+    .note(Pitch::new(G, 6), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::HALF)
+    .note(Pitch::new(C, 6), Duration::HALF)
+    .note(Pitch::new(B, 6), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 6), Duration::QUARTER)
+    .note(Pitch::new(B, 6), Duration::QUARTER)
+    .note(Pitch::new(A, 6), Duration::QUARTER)
+    .note(Pitch::new(G, 6), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::HALF)
+    .note(Pitch::new(C, 6), Duration::HALF)
+    .note(Pitch::new(B, 6), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 5), Duration::QUARTER)
+    .note(Pitch::new(B, 5), Duration::QUARTER)
+    .note(Pitch::new(A, 5), Duration::QUARTER)
+    .note(Pitch::new(G, 5), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::HALF)
+    .note(Pitch::new(C, 5), Duration::HALF)
+    .note(Pitch::new(B, 6), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 6), Duration::QUARTER)
+    .note(Pitch::new(B, 6), Duration::QUARTER)
+    .note(Pitch::new(A, 6), Duration::QUARTER)
+    .note(Pitch::new(G, 6), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::HALF)
+    .note(Pitch::new(C, 6), Duration::HALF)
+    .note(Pitch::new(B, 5), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 5), Duration::QUARTER)
+    .note(Pitch::new(B, 5), Duration::QUARTER)
+    .note(Pitch::new(A, 5), Duration::QUARTER)
+    .note(Pitch::new(G, 5), Duration::QUARTER)//
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 7), Duration::QUARTER)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(F, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(G, 6), Duration::QUARTER)
+    .note(Pitch::new(F, 6), Duration::EIGHTH)
+    .note(Pitch::new(E, 6), Duration::EIGHTH)
+    .note(Pitch::new(D, 6), Duration::EIGHTH)
+    .note(Pitch::new(E, 6), Duration::EIGHTH)
+    .note(Pitch::new(F, 6), Duration::QUARTER)
+    .note(Pitch::new(D, 6), Duration::HALF)
+    .note(Pitch::new(C, 5), Duration::HALF)
+    .note(Pitch::new(B, 5), Duration::HALF)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::EIGHTH)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(E, 7), Duration::QUARTER)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(D, 7), Duration::EIGHTH)
+    .note(Pitch::new(C, 7), Duration::QUARTER)
+    .note(Pitch::new(B, 7), Duration::QUARTER)
+    .note(Pitch::new(C, 6), Duration::QUARTER)
+    .note(Pitch::new(B, 6), Duration::QUARTER)
+    .note(Pitch::new(A, 6), Duration::QUARTER)
+    .note(Pitch::new(G, 6), Duration::QUARTER);//
@@ -0,0 +1,191 @@
+//! Typed pitches, to write melodies without the `music::NOTE_*` frequencies.
+
+use crate::music;
+use core::fmt;
+
+// B0 and D#8, the lowest and highest notes of the `music` table
+const LOWEST_TABLE_PITCH: u8 = 23;
+const HIGHEST_TABLE_PITCH: u8 = 111;
+const HIGHEST_PITCH: u8 = 127;
+
+/// The twelve notes of an octave, `Cs` being C sharp (or D flat).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PitchClass {
+    C,
+    Cs,
+    D,
+    Ds,
+    E,
+    F,
+    Fs,
+    G,
+    Gs,
+    A,
+    As,
+    B,
+}
+
+impl PitchClass {
+    const ALL: [PitchClass; 12] = [
+        PitchClass::C,
+        PitchClass::Cs,
+        PitchClass::D,
+        PitchClass::Ds,
+        PitchClass::E,
+        PitchClass::F,
+        PitchClass::Fs,
+        PitchClass::G,
+        PitchClass::Gs,
+        PitchClass::A,
+        PitchClass::As,
+        PitchClass::B,
+    ];
+
+    /// Semitones above C, from 0 to 11.
+    pub const fn semitone(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_semitone(semitone: u8) -> Self {
+        Self::ALL[(semitone % 12) as usize]
+    }
+}
+
+/// A note of the equal-tempered scale, kept as its MIDI number: C4 is 60 and A4
+/// is 69. Pitches go from C-1 to G9, moving them out of this range saturates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pitch(u8);
+
+impl Pitch {
+    pub const fn new(class: PitchClass, octave: i8) -> Self {
+        Self::from_midi(0).transpose((octave as i16 + 1) * 12 + class.semitone() as i16)
+    }
+
+    pub const fn from_midi(number: u8) -> Self {
+        Self(if number > HIGHEST_PITCH {
+            HIGHEST_PITCH
+        } else {
+            number
+        })
+    }
+
+    /// Pitch of a `music::NOTE_*` frequency, `None` for `music::REST` and the
+    /// frequencies that aren't in the table.
+    pub fn from_frequency(frequency: f64) -> Option<Self> {
+        (LOWEST_TABLE_PITCH..=HIGHEST_TABLE_PITCH)
+            .map(Self)
+            .find(|pitch| pitch.frequency() == frequency)
+    }
+
+    pub const fn midi(self) -> u8 {
+        self.0
+    }
+
+    pub const fn class(self) -> PitchClass {
+        PitchClass::from_semitone(self.0)
+    }
+
+    /// Octave of the scientific pitch notation, middle C being C4.
+    pub const fn octave(self) -> i8 {
+        (self.0 / 12) as i8 - 1
+    }
+
+    /// Moves the pitch up by `semitones`, or down when negative.
+    pub const fn transpose(self, semitones: i16) -> Self {
+        let number = self.0 as i16 + semitones;
+        if number < 0 {
+            Self(0)
+        } else if number > HIGHEST_PITCH as i16 {
+            Self(HIGHEST_PITCH)
+        } else {
+            Self(number as u8)
+        }
+    }
+
+    pub const fn shift_octaves(self, octaves: i8) -> Self {
+        self.transpose(octaves as i16 * 12)
+    }
+
+    /// Frequency in Hertz, the one of the `music::NOTE_*` constant for the pitches
+    /// in the table.
+    pub fn frequency(self) -> f64 {
+        // The pitches out of the table are whole octaves away from one in it
+        let mut pitch = self.0;
+        let mut scale = 1.0;
+        while pitch < LOWEST_TABLE_PITCH {
+            pitch += 12;
+            scale /= 2.0;
+        }
+        while pitch > HIGHEST_TABLE_PITCH {
+            pitch -= 12;
+            scale *= 2.0;
+        }
+        let pitch = Self(pitch);
+        let frequency = music::note_frequency(pitch.octave() as u8, pitch.class().semitone());
+        frequency.unwrap() * scale
+    }
+
+    /// Frequency rounded to the nearest Hertz.
+    pub fn frequency_hz(self) -> u32 {
+        (self.frequency() + 0.5) as u32
+    }
+
+    /// Frequency for the LEDC timer of the buzzer.
+    #[cfg(target_arch = "xtensa")]
+    pub fn rate(self) -> esp_hal::time::Rate {
+        esp_hal::time::Rate::from_hz(self.frequency_hz())
+    }
+}
+
+impl fmt::Display for PitchClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [&str; 12] = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        f.write_str(NAMES[usize::from(self.semitone())])
+    }
+}
+
+/// Scientific pitch notation, like `C#4`.
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.class(), self.octave())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PitchClass::*;
+    use super::*;
+    use crate::music::*;
+
+    #[test]
+    fn pitches_match_the_note_table() {
+        assert_eq!(Pitch::new(A, 4).midi(), 69);
+        assert_eq!(Pitch::new(C, 4).frequency(), NOTE_C4);
+        assert_eq!(Pitch::new(B, 0).frequency(), NOTE_B0);
+        assert_eq!(Pitch::new(Ds, 8).frequency_hz(), 4978);
+        assert_eq!(Pitch::from_frequency(NOTE_FS5), Some(Pitch::new(Fs, 5)));
+        assert_eq!(Pitch::from_frequency(REST), None);
+        assert_eq!(Pitch::from_frequency(441.0), None);
+    }
+
+    #[test]
+    fn pitches_out_of_the_table_use_octaves() {
+        assert_eq!(Pitch::new(B, -1).frequency(), NOTE_B0 / 2.0);
+        assert_eq!(Pitch::new(E, 8).frequency(), NOTE_E7 * 2.0);
+    }
+
+    #[test]
+    fn transposing_wraps_the_octave_and_saturates() {
+        let b4 = Pitch::new(B, 4);
+        assert_eq!(b4.transpose(1), Pitch::new(C, 5));
+        assert_eq!(b4.transpose(-11), Pitch::new(C, 4));
+        assert_eq!(b4.shift_octaves(-2), Pitch::new(B, 2));
+        assert_eq!((b4.class(), b4.octave()), (B, 4));
+        assert_eq!(b4.transpose(-1).to_string(), "A#4");
+        assert_eq!(Pitch::from_midi(0).to_string(), "C-1");
+        assert_eq!(b4.transpose(-100).midi(), 0);
+        assert_eq!(b4.shift_octaves(10), Pitch::from_midi(127));
+    }
+}
@@ -0,0 +1,732 @@
+//! Player for the buzzer task.
+//!
+//! Other tasks send a `Command` to the task owning the buzzer, which feeds it to
+//! `Player::handle` and reports the returned `Status` back. The task plays the
+//! `Player::note` for as long as it says, then calls `Player::advance`:
+//!
+//! ```ignore
+//! loop {
+//!     match player.note() {
+//!         Some(note) => {
+//!             // Tone on for `note.on_ms`, off for `note.off_ms`, unless a command comes in
+//!             player.advance();
+//!         }
+//!         None => {
+//!             player.handle(commands.receive().await);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! A note is only over once `advance` is called, so a note cut by a pause is
+//! played again from its start on resume.
+//!
+//! A `Tune::Duet` has a part for each buzzer. The notes of the parts start and
+//! end at different times, so the task plays `Player::notes` until
+//! `Player::next_change_ms`, where `advance` moves on the parts whose note ends
+//! there. The parts are timed on the same clock, in note lengths rather than
+//! milliseconds, so that they stay together whatever the tempo does. A pause
+//! goes back to the last change rather than to the start of the notes.
+
+use crate::duration::Duration;
+use crate::music;
+use crate::pitch::Pitch;
+use crate::rtttl::{self, Ringtone};
+use crate::score::{Duet, Event, Score};
+use crate::voice::{Articulation, Envelope, Voice};
+use core::slice;
+
+/// Something to play.
+#[derive(Debug, Clone)]
+pub enum Tune {
+    /// A table of `(frequency, divider)` notes like `pink_panther::MELODY`.
+    Melody {
+        notes: &'static [(f64, i16)],
+        tempo: u16,
+    },
+    Ringtone(Ringtone<'static>),
+    /// The events of a `Score`, which can change the tempo along the way.
+    Score {
+        events: &'static [Event],
+        tempo: u16,
+    },
+    /// The two parts of a `Duet`, played together.
+    Duet {
+        melody: &'static [Event],
+        bass: &'static [Event],
+        tempo: u16,
+    },
+}
+
+/// Parts a tune has at most, one for each buzzer.
+pub const PARTS: usize = 2;
+
+impl<const N: usize> From<&'static Score<N>> for Tune {
+    fn from(score: &'static Score<N>) -> Self {
+        Tune::Score {
+            events: score.events(),
+            tempo: score.initial_tempo(),
+        }
+    }
+}
+
+impl<const M: usize, const B: usize> From<&'static Duet<M, B>> for Tune {
+    fn from(duet: &'static Duet<M, B>) -> Self {
+        Tune::Duet {
+            melody: duet.melody.events(),
+            bass: duet.bass.events(),
+            tempo: duet.initial_tempo(),
+        }
+    }
+}
+
+impl Tune {
+    /// Tempo the tune starts at.
+    pub fn tempo(&self) -> u16 {
+        match self {
+            Tune::Melody { tempo, .. } | Tune::Score { tempo, .. } | Tune::Duet { tempo, .. } => {
+                *tempo
+            }
+            Tune::Ringtone(ringtone) => ringtone.tempo,
+        }
+    }
+
+    fn parts(&self) -> [Option<Steps>; PARTS] {
+        match self {
+            Tune::Melody { notes, .. } => [Some(Steps::Melody(notes.iter())), None],
+            Tune::Ringtone(ringtone) => [Some(Steps::Ringtone(ringtone.notes())), None],
+            Tune::Score { events, .. } => [Some(Steps::Score(events.iter())), None],
+            Tune::Duet { melody, bass, .. } => [
+                Some(Steps::Score(melody.iter())),
+                Some(Steps::Score(bass.iter())),
+            ],
+        }
+    }
+}
+
+// What the tunes are made of for the player. The notes of the tables keep their
+// frequency, their pitch is only for display.
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Sound(f64, Option<Pitch>, Duration),
+    Tempo(u16),
+}
+
+#[derive(Debug, Clone)]
+enum Steps {
+    Melody(slice::Iter<'static, (f64, i16)>),
+    Ringtone(rtttl::Notes<'static>),
+    Score(slice::Iter<'static, Event>),
+}
+
+impl Iterator for Steps {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let table_note = |(frequency, divider): (f64, i16)| {
+            let pitch = Pitch::from_frequency(frequency);
+            Step::Sound(frequency, pitch, Duration::from_divider(divider))
+        };
+        match self {
+            Steps::Melody(notes) => notes.next().copied().map(table_note),
+            Steps::Ringtone(notes) => notes.next().map(table_note),
+            Steps::Score(events) => events.next().map(|event| match *event {
+                Event::Note(pitch, duration) => {
+                    Step::Sound(pitch.frequency(), Some(pitch), duration)
+                }
+                Event::Rest(duration) => Step::Sound(music::REST, None, duration),
+                Event::Tempo(tempo) => Step::Tempo(tempo),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Plays a tune at its own tempo, replacing the one playing.
+    Play(Tune),
+    Stop,
+    Pause,
+    Resume,
+    /// Beats per minute of the tune playing, from its next note until its next
+    /// tempo change. A new tune starts at its own tempo again.
+    SetTempo(u16),
+    /// Percent of the full volume, for all the tunes from the next step of the
+    /// note playing.
+    SetVolume(u8),
+    SetEnvelope(Envelope),
+    /// How long the notes are held, from the next note.
+    SetArticulation(Articulation),
+}
+
+impl Command {
+    /// Whether the note being played must be cut short.
+    pub fn interrupts_note(&self) -> bool {
+        matches!(self, Command::Play(_) | Command::Stop | Command::Pause)
+    }
+}
+
+/// Reported back by the player task. `pitch` is the note starting, `None` for
+/// a rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Started {
+        notes: usize,
+        pitch: Option<Pitch>,
+    },
+    /// `played` notes of `notes` are over.
+    Progress {
+        played: usize,
+        notes: usize,
+        pitch: Option<Pitch>,
+    },
+    Paused,
+    Resumed,
+    Stopped,
+    Finished,
+    TempoChanged(u16),
+    VolumeChanged(u8),
+}
+
+/// A note to play, the buzzer is silent for a `music::REST` frequency.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub frequency: f64,
+    /// `None` for a rest and the frequencies out of the `music` table.
+    pub pitch: Option<Pitch>,
+    pub on_ms: u32,
+    /// Silence after the note, set by the `Articulation`. The envelope is
+    /// released in it.
+    pub off_ms: u32,
+}
+
+impl Note {
+    pub fn is_rest(&self) -> bool {
+        self.frequency == music::REST
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sound {
+    frequency: f64,
+    pitch: Option<Pitch>,
+    duration: Duration,
+}
+
+// A line of the tune, `Player` has one for each buzzer
+#[derive(Debug, Clone, Default)]
+struct Part {
+    // Steps after `current`, `None` when there is no tune or the tune has no
+    // such part
+    steps: Option<Steps>,
+    current: Option<Sound>,
+    // Position `current` starts at, from the start of the tune
+    start: Duration,
+}
+
+impl Part {
+    fn end(&self) -> Option<Duration> {
+        self.current.map(|sound| self.start + sound.duration)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Player {
+    parts: [Part; PARTS],
+    // Position the notes playing were started or went on from
+    clock: Duration,
+    // Notes over in all the parts
+    played: usize,
+    total: usize,
+    tempo: u16,
+    // Where the tempo was last changed, in note lengths and in milliseconds, so
+    // that the rounding of the milliseconds doesn't add up along the tune
+    tempo_start: Duration,
+    tempo_start_ms: u32,
+    paused: bool,
+    voice: Voice,
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle(&mut self, command: Command) -> Option<Status> {
+        match command {
+            Command::Play(tune) => {
+                let parts = tune.parts();
+                let total = parts
+                    .iter()
+                    .flatten()
+                    .map(|steps| {
+                        steps
+                            .clone()
+                            .filter(|step| matches!(step, Step::Sound(..)))
+                            .count()
+                    })
+                    .sum();
+                *self = Self {
+                    parts: parts.map(|steps| Part {
+                        steps,
+                        ..Part::default()
+                    }),
+                    total,
+                    tempo: tune.tempo(),
+                    voice: self.voice,
+                    ..Self::new()
+                };
+                for part in 0..PARTS {
+                    self.next_sound(part);
+                }
+                if !self.is_playing() {
+                    self.reset();
+                    return Some(Status::Finished);
+                }
+                Some(Status::Started {
+                    notes: total,
+                    pitch: self.current_pitch(),
+                })
+            }
+            Command::Stop => {
+                let was_playing = self.is_playing() || self.paused;
+                self.reset();
+                was_playing.then_some(Status::Stopped)
+            }
+            Command::Pause if self.is_playing() => {
+                self.paused = true;
+                Some(Status::Paused)
+            }
+            Command::Resume if self.paused => {
+                self.paused = false;
+                Some(Status::Resumed)
+            }
+            Command::Pause | Command::Resume => None,
+            // A tempo of 0 would make the notes endless
+            Command::SetTempo(tempo) if self.has_tune() => {
+                self.change_tempo(tempo.max(1));
+                Some(Status::TempoChanged(self.tempo))
+            }
+            Command::SetTempo(_) => None,
+            Command::SetVolume(volume) => {
+                self.voice.volume = volume.min(100);
+                Some(Status::VolumeChanged(self.voice.volume))
+            }
+            Command::SetEnvelope(envelope) => {
+                self.voice.envelope = envelope;
+                None
+            }
+            Command::SetArticulation(articulation) => {
+                self.voice.articulation = articulation;
+                None
+            }
+        }
+    }
+
+    /// The note to play now in the first part, `None` when idle or paused.
+    pub fn note(&self) -> Option<Note> {
+        self.part_note(0)
+    }
+
+    /// The notes playing now in each part, whole even when they started before
+    /// the last change. `None` for the parts that are over or that the tune
+    /// doesn't have.
+    pub fn notes(&self) -> [Option<Note>; PARTS] {
+        core::array::from_fn(|part| self.part_note(part))
+    }
+
+    /// How long the note of `part` has been playing at the last change. Like the
+    /// length of the notes it follows the current tempo, even when the note
+    /// started before a tempo change.
+    pub fn elapsed_ms(&self, part: usize) -> Option<u32> {
+        self.part_note(part)?;
+        let start = self.parts[part].start;
+        Some((self.clock - start).millis(self.tempo))
+    }
+
+    /// Time from the last change until the next one, when a note of one of the
+    /// parts ends.
+    pub fn next_change_ms(&self) -> Option<u32> {
+        if !self.is_playing() {
+            return None;
+        }
+        let change = self.next_change()?;
+        Some(self.millis_at(change) - self.millis_at(self.clock))
+    }
+
+    fn part_note(&self, part: usize) -> Option<Note> {
+        if self.paused {
+            return None;
+        }
+        let Sound {
+            frequency,
+            pitch,
+            duration,
+        } = self.parts[part].current?;
+        let duration = duration.millis(self.tempo);
+        // Rests are silent all along
+        let off_ms = if frequency == music::REST {
+            0
+        } else {
+            self.voice.articulation.gap_ms(duration)
+        };
+        Some(Note {
+            frequency,
+            pitch,
+            on_ms: duration - off_ms,
+            off_ms,
+        })
+    }
+
+    /// Moves on once the current note has been played, in all the parts whose
+    /// note ends at the next change.
+    pub fn advance(&mut self) -> Option<Status> {
+        if !self.is_playing() {
+            return None;
+        }
+        let change = self.next_change()?;
+        self.clock = change;
+        for part in 0..PARTS {
+            if self.parts[part].end() == Some(change) {
+                self.played += 1;
+                self.next_sound(part);
+            }
+        }
+        if !self.is_playing() {
+            self.reset();
+            return Some(Status::Finished);
+        }
+        Some(Status::Progress {
+            played: self.played,
+            notes: self.total,
+            pitch: self.current_pitch(),
+        })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.parts.iter().any(|part| part.current.is_some()) && !self.paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// What the notes sound like, whether a tune is playing or not.
+    pub fn voice(&self) -> Voice {
+        self.voice
+    }
+
+    fn has_tune(&self) -> bool {
+        self.parts.iter().any(|part| part.steps.is_some())
+    }
+
+    // Position the first of the notes playing ends at
+    fn next_change(&self) -> Option<Duration> {
+        self.parts.iter().filter_map(Part::end).min()
+    }
+
+    // Time from the start of the tune to `position`, not before the last tempo
+    // change
+    fn millis_at(&self, position: Duration) -> u32 {
+        self.tempo_start_ms + (position - self.tempo_start).millis(self.tempo)
+    }
+
+    // Changes the tempo from the clock on
+    fn change_tempo(&mut self, tempo: u16) {
+        self.tempo_start_ms = self.millis_at(self.clock);
+        self.tempo_start = self.clock;
+        self.tempo = tempo;
+    }
+
+    // Moves `current` of `part` to its next sound, starting at the clock.
+    // The tempo changes before it apply to all the parts from there on.
+    fn next_sound(&mut self, part: usize) {
+        let mut steps = self.parts[part].steps.take();
+        let mut current = None;
+        while let Some(step) = steps.as_mut().and_then(Iterator::next) {
+            match step {
+                Step::Sound(frequency, pitch, duration) => {
+                    current = Some(Sound {
+                        frequency,
+                        pitch,
+                        duration,
+                    });
+                    break;
+                }
+                Step::Tempo(tempo) => self.change_tempo(tempo),
+            }
+        }
+        self.parts[part] = Part {
+            steps,
+            current,
+            start: self.clock,
+        };
+    }
+
+    // Pitch of the melody, the first part
+    fn current_pitch(&self) -> Option<Pitch> {
+        self.parts[0].current.and_then(|sound| sound.pitch)
+    }
+
+    fn reset(&mut self) {
+        *self = Self {
+            voice: self.voice,
+            ..Self::new()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::*;
+    use crate::pitch::{Pitch, PitchClass::*};
+    use crate::voice::{Articulation, Envelope, Voice};
+
+    const MELODY: [(f64, i16); 3] = [(NOTE_C4, 4), (REST, 8), (NOTE_E4, -4)];
+
+    fn melody() -> Command {
+        Command::Play(Tune::Melody {
+            notes: &MELODY,
+            tempo: 120,
+        })
+    }
+
+    #[test]
+    fn plays_the_notes_in_order() {
+        let mut player = Player::new();
+        assert_eq!(player.note(), None);
+        assert_eq!(
+            player.handle(melody()),
+            Some(Status::Started {
+                notes: 3,
+                pitch: Some(Pitch::new(C, 4))
+            })
+        );
+
+        let note = player.note().unwrap();
+        assert_eq!(
+            (note.frequency, note.on_ms, note.off_ms),
+            (NOTE_C4, 450, 50)
+        );
+        assert_eq!(
+            player.advance(),
+            Some(Status::Progress {
+                played: 1,
+                notes: 3,
+                pitch: None
+            })
+        );
+        let rest = player.note().unwrap();
+        assert!(rest.is_rest());
+        assert_eq!(rest.pitch, None);
+        assert_eq!((rest.on_ms, rest.off_ms), (250, 0));
+        player.advance();
+        assert_eq!(player.note().unwrap().on_ms, 675);
+        assert_eq!(player.advance(), Some(Status::Finished));
+        assert_eq!(player.note(), None);
+        assert_eq!(player.advance(), None);
+    }
+
+    #[test]
+    fn pause_replays_the_cut_note_on_resume() {
+        let mut player = Player::new();
+        player.handle(melody());
+        player.advance();
+        let rest = player.note();
+
+        assert_eq!(player.handle(Command::Pause), Some(Status::Paused));
+        assert!(player.is_paused());
+        assert_eq!(player.note(), None);
+        assert_eq!(player.advance(), None);
+        assert_eq!(player.handle(Command::Pause), None);
+
+        assert_eq!(player.handle(Command::Resume), Some(Status::Resumed));
+        assert_eq!(player.note(), rest);
+        assert_eq!(player.handle(Command::Resume), None);
+    }
+
+    #[test]
+    fn stop_and_play_replace_the_tune() {
+        let mut player = Player::new();
+        assert_eq!(player.handle(Command::Stop), None);
+        player.handle(melody());
+        player.handle(Command::Pause);
+        assert_eq!(player.handle(Command::Stop), Some(Status::Stopped));
+        assert_eq!(player.handle(Command::Resume), None);
+        assert_eq!(player.note(), None);
+
+        let ringtone = Ringtone::parse("Two:d=8,o=5,b=100:c,d").unwrap();
+        player.handle(melody());
+        player.advance();
+        assert_eq!(
+            player.handle(Command::Play(Tune::Ringtone(ringtone))),
+            Some(Status::Started {
+                notes: 2,
+                pitch: Some(Pitch::new(C, 5))
+            })
+        );
+        assert_eq!(player.note().unwrap().frequency, NOTE_C5);
+
+        let empty = Tune::Melody {
+            notes: &[],
+            tempo: 120,
+        };
+        assert_eq!(player.handle(Command::Play(empty)), Some(Status::Finished));
+        assert!(!player.is_playing());
+    }
+
+    #[test]
+    fn tempo_changes_until_the_next_tune() {
+        let mut player = Player::new();
+        assert_eq!(player.handle(Command::SetTempo(60)), None);
+        player.handle(melody());
+        assert_eq!(
+            player.handle(Command::SetTempo(60)),
+            Some(Status::TempoChanged(60))
+        );
+        assert_eq!(player.note().unwrap().on_ms, 900);
+        assert_eq!(
+            player.handle(Command::SetTempo(0)),
+            Some(Status::TempoChanged(1))
+        );
+
+        player.handle(melody());
+        assert_eq!(player.note().unwrap().on_ms, 450);
+    }
+
+    #[test]
+    fn scores_change_the_tempo_along_the_way() {
+        static SCORE: Score<4> = Score::new(120)
+            .note(Pitch::new(C, 4), Duration::QUARTER)
+            .tempo(60)
+            .rest(Duration::QUARTER)
+            .note(Pitch::new(E, 4), Duration::EIGHTH.triplet());
+
+        let mut player = Player::new();
+        assert_eq!(
+            player.handle(Command::Play(Tune::from(&SCORE))),
+            Some(Status::Started {
+                notes: 3,
+                pitch: Some(Pitch::new(C, 4))
+            })
+        );
+        assert_eq!(player.note().unwrap().on_ms, 450);
+        player.advance();
+        assert_eq!(player.note().unwrap().on_ms, 1000);
+        player.advance();
+        let note = player.note().unwrap();
+        assert_eq!(
+            (note.frequency, note.on_ms, note.off_ms),
+            (NOTE_E4, 300, 33)
+        );
+    }
+
+    #[test]
+    fn duet_parts_keep_in_time() {
+        static DUET: Duet<4, 3> = Duet::new(
+            Score::new(120)
+                .note(Pitch::new(E, 5), Duration::QUARTER)
+                .note(Pitch::new(D, 5), Duration::QUARTER)
+                .tempo(60)
+                .note(Pitch::new(C, 5), Duration::HALF),
+            Score::new(120)
+                .note(Pitch::new(C, 3), Duration::HALF.dotted())
+                .rest(Duration::EIGHTH)
+                .note(Pitch::new(G, 2), Duration::EIGHTH),
+        );
+        let mut player = Player::new();
+        player.handle(Command::SetArticulation(Articulation::Legato));
+        assert_eq!(
+            player.handle(Command::Play(Tune::from(&DUET))),
+            Some(Status::Started {
+                notes: 6,
+                pitch: Some(Pitch::new(E, 5))
+            })
+        );
+        let [melody, bass] = player.notes().map(Option::unwrap);
+        assert_eq!((melody.frequency, melody.on_ms), (NOTE_E5, 500));
+        assert_eq!((bass.frequency, bass.on_ms), (NOTE_C3, 1500));
+        assert_eq!(player.next_change_ms(), Some(500));
+
+        // Only the melody moves on, the bass note goes on
+        assert_eq!(
+            player.advance(),
+            Some(Status::Progress {
+                played: 1,
+                notes: 6,
+                pitch: Some(Pitch::new(D, 5))
+            })
+        );
+        assert_eq!(player.elapsed_ms(0), Some(0));
+        assert_eq!(player.elapsed_ms(1), Some(500));
+        assert_eq!(player.next_change_ms(), Some(500));
+
+        // The tempo change of the melody slows the bass down too
+        player.advance();
+        assert_eq!(player.notes()[0].unwrap().on_ms, 2000);
+        assert_eq!(player.elapsed_ms(1), Some(2000));
+        assert_eq!(player.notes()[1].unwrap().on_ms, 3000);
+        assert_eq!(player.next_change_ms(), Some(1000));
+        player.advance();
+        assert!(player.notes()[1].unwrap().is_rest());
+        assert_eq!(player.next_change_ms(), Some(500));
+        player.advance();
+        assert_eq!(player.notes()[1].unwrap().frequency, NOTE_G2);
+        assert_eq!(player.elapsed_ms(0), Some(1500));
+
+        // Both end together
+        assert_eq!(player.next_change_ms(), Some(500));
+        assert_eq!(player.advance(), Some(Status::Finished));
+        assert_eq!(player.notes(), [None, None]);
+    }
+
+    #[test]
+    fn single_part_tunes_leave_the_second_part_silent() {
+        let mut player = Player::new();
+        player.handle(melody());
+        assert_eq!(player.notes()[1], None);
+        assert_eq!(player.elapsed_ms(1), None);
+        assert_eq!(player.next_change_ms(), Some(500));
+        player.handle(Command::Pause);
+        assert_eq!(player.next_change_ms(), None);
+    }
+
+    #[test]
+    fn only_play_stop_and_pause_cut_the_note() {
+        assert!(melody().interrupts_note());
+        assert!(Command::Stop.interrupts_note());
+        assert!(Command::Pause.interrupts_note());
+        assert!(!Command::Resume.interrupts_note());
+        assert!(!Command::SetTempo(90).interrupts_note());
+        assert!(!Command::SetVolume(50).interrupts_note());
+    }
+
+    #[test]
+    fn voice_is_kept_from_one_tune_to_the_next() {
+        let mut player = Player::new();
+        assert_eq!(
+            player.handle(Command::SetVolume(150)),
+            Some(Status::VolumeChanged(100))
+        );
+        player.handle(Command::SetVolume(40));
+        player.handle(Command::SetEnvelope(Envelope::FLAT));
+        player.handle(Command::SetArticulation(Articulation::Staccato));
+        player.handle(melody());
+        let note = player.note().unwrap();
+        assert_eq!((note.on_ms, note.off_ms), (250, 250));
+
+        player.handle(Command::SetArticulation(Articulation::Legato));
+        assert_eq!(player.note().unwrap().off_ms, 0);
+        player.handle(Command::Stop);
+        player.handle(melody());
+        assert_eq!(
+            player.voice(),
+            Voice {
+                envelope: Envelope::FLAT,
+                articulation: Articulation::Legato,
+                volume: 40,
+            }
+        );
+    }
+}
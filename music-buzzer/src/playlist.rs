@@ -0,0 +1,140 @@
+//! Order the tracks of the library are played in.
+
+/// Indices of `N` tracks, played in a loop in order or shuffled.
+#[derive(Debug, Clone)]
+pub struct Playlist<const N: usize> {
+    order: [usize; N],
+    position: usize,
+    shuffled: bool,
+}
+
+impl<const N: usize> Default for Playlist<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Playlist<N> {
+    /// Starts on the first track, in order.
+    pub fn new() -> Self {
+        assert!(N > 0, "a playlist needs tracks");
+        Self {
+            order: core::array::from_fn(|idx| idx),
+            position: 0,
+            shuffled: false,
+        }
+    }
+
+    /// Index of the track to play.
+    pub fn current(&self) -> usize {
+        self.order[self.position]
+    }
+
+    /// Position of the current track in the playlist, from 0.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves to the next track, the first one after the last.
+    pub fn skip_forward(&mut self) -> usize {
+        self.position = (self.position + 1) % N;
+        self.current()
+    }
+
+    /// Moves to the previous track, the last one before the first.
+    pub fn skip_back(&mut self) -> usize {
+        self.position = (self.position + N - 1) % N;
+        self.current()
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffled
+    }
+
+    /// Shuffles the other tracks to come after the current one. `random` gives
+    /// random numbers, e.g. from the hardware `Rng`.
+    pub fn shuffle(&mut self, mut random: impl FnMut() -> u32) {
+        let current = self.current();
+        // Fisher-Yates, the current track is then moved to the front
+        for idx in (1..N).rev() {
+            let other = random() as usize % (idx + 1);
+            self.order.swap(idx, other);
+        }
+        let at = self
+            .order
+            .iter()
+            .position(|&track| track == current)
+            .unwrap();
+        self.order.swap(0, at);
+        self.position = 0;
+        self.shuffled = true;
+    }
+
+    /// Goes back to the library order, from the current track.
+    pub fn unshuffle(&mut self) {
+        let current = self.current();
+        self.order = core::array::from_fn(|idx| idx);
+        self.position = current;
+        self.shuffled = false;
+    }
+
+    /// Shuffles or unshuffles, returns whether the playlist is now shuffled.
+    pub fn toggle_shuffle(&mut self, random: impl FnMut() -> u32) -> bool {
+        if self.shuffled {
+            self.unshuffle();
+        } else {
+            self.shuffle(random);
+        }
+        self.shuffled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic stand-in for the hardware generator
+    fn lcg(mut state: u32) -> impl FnMut() -> u32 {
+        move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            state >> 8
+        }
+    }
+
+    #[test]
+    fn next_and_previous_loop() {
+        let mut playlist = Playlist::<3>::new();
+        assert_eq!(playlist.current(), 0);
+        assert_eq!(playlist.skip_back(), 2);
+        assert_eq!(playlist.skip_forward(), 0);
+        assert_eq!(playlist.skip_forward(), 1);
+        assert_eq!(playlist.skip_forward(), 2);
+        assert_eq!(playlist.skip_forward(), 0);
+    }
+
+    #[test]
+    fn shuffle_keeps_the_current_track_and_plays_all_once() {
+        let mut playlist = Playlist::<8>::new();
+        playlist.skip_forward();
+        playlist.skip_forward();
+        assert!(playlist.toggle_shuffle(lcg(7)));
+        assert_eq!(playlist.current(), 2);
+        assert_eq!(playlist.position(), 0);
+
+        let mut played: Vec<_> = (0..8).map(|_| playlist.skip_forward()).collect();
+        assert_ne!(played, [3, 4, 5, 6, 7, 0, 1, 2]);
+        assert_eq!(played.last(), Some(&2));
+        played.sort();
+        assert_eq!(played, [0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn unshuffle_goes_on_from_the_current_track() {
+        let mut playlist = Playlist::<5>::new();
+        playlist.shuffle(lcg(1));
+        let current = playlist.skip_forward();
+        assert!(!playlist.toggle_shuffle(lcg(1)));
+        assert_eq!(playlist.current(), current);
+        assert_eq!(playlist.skip_forward(), (current + 1) % 5);
+    }
+}
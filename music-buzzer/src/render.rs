@@ -0,0 +1,310 @@
+//! Renders tunes the way the buzzer plays them, to check them on the host.
+//!
+//! `Timeline` gives when each note starts and how long it sounds, with the same
+//! `Player` as the firmware. `render` turns it into the samples of the pulse wave
+//! the LEDC channel outputs, its duty stepped through the envelope of the notes,
+//! and `wav_header` makes a WAV file of them. `examples/render.rs` does both from
+//! the command line.
+//!
+//! The parts of a duet are mixed, as the two buzzers would be heard together.
+
+use crate::player::{Command, Note, Player, Tune, PARTS};
+use crate::voice::{Voice, DUTY_RANGE, STEP_MS};
+
+/// Peak of the square wave at 50% duty, half of the 16-bit range to leave some
+/// headroom.
+pub const AMPLITUDE: i16 = i16::MAX / 2;
+
+/// A note of the timeline, `start_ms` after the start of the tune.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timed {
+    pub start_ms: u32,
+    /// Part of the tune playing it, 0 for the melody.
+    pub part: usize,
+    pub note: Note,
+    pub voice: Voice,
+}
+
+impl Timed {
+    /// When the next note starts, after the note and its silence.
+    pub fn end_ms(&self) -> u32 {
+        self.start_ms + self.note.on_ms + self.note.off_ms
+    }
+}
+
+/// Iterator over the notes of a tune, in order of their start. The notes
+/// starting together come in the order of their parts.
+#[derive(Debug)]
+pub struct Timeline {
+    player: Player,
+    elapsed_ms: u32,
+    // Parts whose note starts at `elapsed_ms` and hasn't been given yet
+    starting: [bool; PARTS],
+}
+
+impl Timeline {
+    /// Plays the tune with the default `Voice`, like the firmware.
+    pub fn new(tune: Tune) -> Self {
+        Self::with_voice(tune, Voice::default())
+    }
+
+    pub fn with_voice(tune: Tune, voice: Voice) -> Self {
+        let mut player = Player::new();
+        player.handle(Command::SetVolume(voice.volume));
+        player.handle(Command::SetEnvelope(voice.envelope));
+        player.handle(Command::SetArticulation(voice.articulation));
+        player.handle(Command::Play(tune));
+        Self {
+            starting: player.notes().map(|note| note.is_some()),
+            player,
+            elapsed_ms: 0,
+        }
+    }
+}
+
+impl Iterator for Timeline {
+    type Item = Timed;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(part) = self.starting.iter().position(|&starting| starting) {
+                self.starting[part] = false;
+                return Some(Timed {
+                    start_ms: self.elapsed_ms,
+                    part,
+                    note: self.player.notes()[part]?,
+                    voice: self.player.voice(),
+                });
+            }
+            self.elapsed_ms += self.player.next_change_ms()?;
+            self.player.advance();
+            self.starting = core::array::from_fn(|part| self.player.elapsed_ms(part) == Some(0));
+        }
+    }
+}
+
+/// Number of samples of a timeline ending at `end_ms`.
+pub fn sample_count(end_ms: u32, sample_rate: u32) -> u32 {
+    (u64::from(end_ms) * u64::from(sample_rate) / 1000) as u32
+}
+
+/// Passes the 16-bit samples of `timeline` at `sample_rate` to `output`.
+///
+/// Like the LEDC timer the frequencies are truncated to whole Hertz, and the
+/// notes start on the sample of their start time so that rounding doesn't drift.
+/// The duty changes every `STEP_MS` like in the firmware, and the wave is
+/// centred on 0 whatever its duty so that the envelope doesn't click. The parts
+/// are added up, saturating at the 16-bit range.
+pub fn render(
+    timeline: impl IntoIterator<Item = Timed>,
+    sample_rate: u32,
+    mut output: impl FnMut(i16),
+) {
+    let mut timeline = timeline.into_iter().peekable();
+    let mut playing: [Option<Timed>; PARTS] = [None; PARTS];
+    for sample in 0.. {
+        while let Some(timed) =
+            timeline.next_if(|timed| sample_count(timed.start_ms, sample_rate) <= sample)
+        {
+            playing[timed.part] = Some(timed);
+        }
+        let over = |timed: &Timed| sample >= sample_count(timed.end_ms(), sample_rate);
+        if timeline.peek().is_none() && playing.iter().flatten().all(over) {
+            return;
+        }
+        let mixed: i32 = playing
+            .iter()
+            .flatten()
+            .filter(|timed| !over(timed))
+            .map(|timed| i32::from(pulse(timed, sample, sample_rate)))
+            .sum();
+        output(mixed.clamp(i16::MIN.into(), i16::MAX.into()) as i16);
+    }
+}
+
+// Sample of the note of `timed` at `sample` from the start of the timeline
+fn pulse(timed: &Timed, sample: u32, sample_rate: u32) -> i16 {
+    let rate = u64::from(sample_rate);
+    let range = u64::from(DUTY_RANGE);
+    let sample = u64::from(sample - sample_count(timed.start_ms, sample_rate));
+    let elapsed_ms = (sample * 1000 / rate) as u32;
+    let step_ms = elapsed_ms - elapsed_ms % STEP_MS;
+    let duty = u64::from(timed.voice.duty(step_ms, timed.note.on_ms));
+    if timed.note.is_rest() || duty == 0 {
+        return 0;
+    }
+    // Position in the period, in duty counts, high until the duty
+    let frequency = u64::from(timed.note.frequency as u32);
+    let phase = sample * frequency * range / rate % range;
+    let peak = 2 * AMPLITUDE as u64;
+    if phase < duty {
+        (peak * (range - duty) / range) as i16
+    } else {
+        -((peak * duty / range) as i16)
+    }
+}
+
+/// Header of a mono 16-bit PCM WAV file of `samples` samples.
+pub fn wav_header(sample_rate: u32, samples: u32) -> [u8; 44] {
+    const BYTES_PER_SAMPLE: u32 = 2;
+    let data_len = samples * BYTES_PER_SAMPLE;
+    let mut header = [0; 44];
+    let fields: [&[u8]; 13] = [
+        b"RIFF",
+        &(36 + data_len).to_le_bytes(),
+        b"WAVE",
+        b"fmt ",
+        &16u32.to_le_bytes(),
+        // PCM, one channel
+        &1u16.to_le_bytes(),
+        &1u16.to_le_bytes(),
+        &sample_rate.to_le_bytes(),
+        &(sample_rate * BYTES_PER_SAMPLE).to_le_bytes(),
+        &(BYTES_PER_SAMPLE as u16).to_le_bytes(),
+        &16u16.to_le_bytes(),
+        b"data",
+        &data_len.to_le_bytes(),
+    ];
+    let mut pos = 0;
+    for field in fields {
+        header[pos..pos + field.len()].copy_from_slice(field);
+        pos += field.len();
+    }
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duets;
+    use crate::duration::Duration;
+    use crate::music::*;
+
+    const MELODY: [(f64, i16); 4] = [(NOTE_A4, 4), (REST, 8), (NOTE_C5, -8), (NOTE_E5, 16)];
+    const TEMPO: u16 = 150;
+
+    fn tune() -> Tune {
+        Tune::Melody {
+            notes: &MELODY,
+            tempo: TEMPO,
+        }
+    }
+
+    fn timeline() -> Timeline {
+        Timeline::with_voice(tune(), Voice::FLAT)
+    }
+
+    #[test]
+    fn timeline_follows_the_note_durations() {
+        let song = Song::new(TEMPO);
+        let mut start_ms = 0;
+        let notes: Vec<_> = timeline().collect();
+        assert_eq!(notes.len(), MELODY.len());
+        for (timed, (frequency, divider)) in notes.iter().zip(MELODY) {
+            let duration = song.calc_note_duration(divider);
+            assert_eq!(timed.start_ms, start_ms);
+            assert_eq!(timed.note.frequency, frequency);
+            assert_eq!(timed.end_ms() - timed.start_ms, duration);
+            // The 10% gap between notes of `main.rs`, rests are silent all along
+            let gap = if frequency == REST { 0 } else { duration / 10 };
+            assert_eq!(timed.note.off_ms, gap);
+            start_ms += duration;
+        }
+    }
+
+    #[test]
+    fn renders_a_square_wave_with_gaps() {
+        let mut samples = Vec::new();
+        render(timeline(), 8000, |sample| samples.push(sample));
+        let end_ms = timeline().last().unwrap().end_ms();
+        assert_eq!(samples.len() as u32, sample_count(end_ms, 8000));
+
+        // A4 at 440 Hz: 8000 / 880 samples per half period, rounded down
+        assert!(samples[..10].iter().all(|&sample| sample == AMPLITUDE));
+        assert_eq!(samples[10], -AMPLITUDE);
+        // The quarter note lasts 400 ms, the last 40 ms are silent
+        assert_ne!(samples[2879], 0);
+        assert!(samples[2880..3200].iter().all(|&sample| sample == 0));
+        // The high and low halves balance over a note
+        let sum: i64 = samples[..2880]
+            .iter()
+            .map(|&sample| i64::from(sample))
+            .sum();
+        assert!(sum.abs() <= i64::from(AMPLITUDE) * 20);
+    }
+
+    #[test]
+    fn renders_the_envelope_and_the_volume() {
+        let voice = Voice {
+            volume: 50,
+            ..Voice::default()
+        };
+        let mut samples = Vec::new();
+        render(Timeline::with_voice(tune(), voice), 8000, |sample| {
+            samples.push(sample)
+        });
+        // Silent at the start of the attack, the wave is centred on 0
+        assert!(samples[..40].iter().all(|&sample| sample == 0));
+        let peak = |range: core::ops::Range<usize>| samples[range].iter().copied().max().unwrap();
+        let sum: i64 = samples[..2880]
+            .iter()
+            .map(|&sample| i64::from(sample))
+            .sum();
+        assert!(sum.abs() <= i64::from(AMPLITUDE) * 20);
+        // Narrow pulses of 153 of the 1024 duty counts once sustained at 30%
+        assert_eq!(
+            peak(2000..2040),
+            (2 * AMPLITUDE as i32 * (1024 - 153) / 1024) as i16
+        );
+        // Released in the gap
+        let loudness = |range: core::ops::Range<usize>| {
+            samples[range]
+                .iter()
+                .map(|&sample| i64::from(sample).abs())
+                .sum::<i64>()
+        };
+        assert!(loudness(2880..2920) > 0);
+        assert!(loudness(3160..3200) < loudness(2880..2920));
+        assert!(samples[3200..3240].iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn duet_parts_are_mixed() {
+        let timeline = Timeline::with_voice(Tune::from(&duets::TWINKLE), Voice::FLAT);
+        let notes: Vec<_> = timeline.collect();
+        assert_eq!(notes.len(), 22);
+        // Both parts start together, then the bass every other melody note
+        assert_eq!((notes[0].part, notes[1].part), (0, 1));
+        assert_eq!(notes[0].start_ms, notes[1].start_ms);
+        let bass_starts: Vec<_> = notes
+            .iter()
+            .filter(|timed| timed.part == 1)
+            .map(|timed| timed.start_ms)
+            .collect();
+        let half_ms = Duration::HALF.millis(duets::TWINKLE_TEMPO);
+        assert_eq!(bass_starts[..3], [0, half_ms, 2 * half_ms]);
+        assert!(notes
+            .windows(2)
+            .all(|pair| pair[0].start_ms <= pair[1].start_ms));
+
+        let mut samples = Vec::new();
+        render(notes, 8000, |sample| samples.push(sample));
+        let end_ms = Duration::new(4, 1).millis(duets::TWINKLE_TEMPO);
+        assert_eq!(samples.len() as u32, sample_count(end_ms, 8000));
+        // Two square waves at 50% duty add up to twice the amplitude
+        assert_eq!(samples.iter().max(), Some(&(2 * AMPLITUDE)));
+        assert_eq!(samples.iter().min(), Some(&(-2 * AMPLITUDE)));
+    }
+
+    #[test]
+    fn wav_header_describes_the_samples() {
+        let header = wav_header(8000, 100);
+        assert_eq!(&header[..4], b"RIFF");
+        assert_eq!(header[4..8], 236u32.to_le_bytes());
+        assert_eq!(&header[8..16], b"WAVEfmt ");
+        assert_eq!(header[24..28], 8000u32.to_le_bytes());
+        assert_eq!(header[28..32], 16000u32.to_le_bytes());
+        assert_eq!(&header[36..40], b"data");
+        assert_eq!(header[40..44], 200u32.to_le_bytes());
+    }
+}
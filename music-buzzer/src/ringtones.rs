@@ -0,0 +1,7 @@
+//! Ringtones in the RTTTL format, see `rtttl::Ringtone::parse`.
+
+pub const NOKIA: &str = "Nokia:d=4,o=5,b=225:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a";
+
+pub const TETRIS: &str = "Tetris:d=4,o=5,b=160:
+    e6,8b,8c6,8d6,16e6,16d6,8c6,8b,a,8a,8c6,e6,8d6,8c6,b,8b,8c6,d6,e6,c6,a,2a,
+    8p,d6,8f6,a6,8g6,8f6,e6,8e6,8c6,e6,8d6,8c6,b,8b,8c6,d6,e6,c6,a,a";
@@ -0,0 +1,409 @@
+//! RTTTL ringtones, the text format of the old mobile phones.
+//!
+//! A ringtone is a name, the default values and the notes, separated by colons:
+//!
+//! ```text
+//! Nokia:d=4,o=5,b=225:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a
+//! ```
+//!
+//! A note is an optional duration (1 for a whole note, up to 32), a letter from `a`
+//! to `h` or `p` for a pause, an optional `#`, an optional octave and an optional `.`
+//! for a dotted note. `d` and `o` are the duration and octave of the notes that have
+//! none, `b` is the tempo in beats per minute. Whitespace and line breaks are allowed
+//! between the values.
+//!
+//! The notes come out as `(frequency, divider)` pairs, like the hand-written melodies,
+//! and `Ringtone::song` gives the `Song` to time them with.
+
+use crate::music::{self, Song};
+use core::fmt;
+
+// Defaults of the RTTTL specification, for ringtones that leave them out
+const DEFAULT_DURATION: u8 = 4;
+const DEFAULT_OCTAVE: u8 = 6;
+const DEFAULT_TEMPO: u16 = 63;
+const MAX_TEMPO: u32 = 900;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The text ends before the colon of the defaults or of the notes.
+    MissingSection,
+    /// A default other than `d`, `o` and `b`, or one without `=`.
+    UnknownDefault,
+    /// A duration other than 1, 2, 4, 8, 16 or 32.
+    InvalidDuration,
+    /// An octave that isn't a single digit.
+    InvalidOctave,
+    /// A tempo of 0 or above 900 beats per minute.
+    InvalidTempo,
+    /// Something else than a note where one was expected.
+    InvalidNote,
+    /// A note out of the frequency table, see `music::note_frequency`.
+    NoteOutOfRange,
+    /// Something else than a `,` or the end of a section after a value.
+    UnexpectedCharacter,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::MissingSection => "missing section",
+            ErrorKind::UnknownDefault => "unknown default",
+            ErrorKind::InvalidDuration => "invalid duration",
+            ErrorKind::InvalidOctave => "invalid octave",
+            ErrorKind::InvalidTempo => "invalid tempo",
+            ErrorKind::InvalidNote => "invalid note",
+            ErrorKind::NoteOutOfRange => "note out of range",
+            ErrorKind::UnexpectedCharacter => "unexpected character",
+        })
+    }
+}
+
+/// Parse error, `line` and `column` start at 1 and count characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub line: u32,
+    pub column: u32,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+/// A parsed ringtone.
+///
+/// The whole text is checked by `parse`, so `notes` can't fail afterwards.
+#[derive(Debug, Clone)]
+pub struct Ringtone<'a> {
+    pub name: &'a str,
+    /// Beats per minute, a beat being a quarter note.
+    pub tempo: u16,
+    notes: Notes<'a>,
+}
+
+impl<'a> Ringtone<'a> {
+    pub fn parse(text: &'a str) -> Result<Self, Error> {
+        let mut cursor = Cursor { text, pos: 0 };
+        let name_end = text
+            .find(':')
+            .ok_or_else(|| cursor.error_at(text.len(), ErrorKind::MissingSection))?;
+        cursor.pos = name_end + 1;
+
+        let mut duration = DEFAULT_DURATION;
+        let mut octave = DEFAULT_OCTAVE;
+        let mut tempo = DEFAULT_TEMPO;
+        loop {
+            cursor.skip_whitespace();
+            if cursor.eat(b':') {
+                break;
+            }
+            let key_pos = cursor.pos;
+            let key = cursor
+                .peek()
+                .ok_or_else(|| cursor.error(ErrorKind::MissingSection))?;
+            cursor.pos += 1;
+            cursor.skip_whitespace();
+            if !cursor.eat(b'=') {
+                return Err(cursor.error_at(key_pos, ErrorKind::UnknownDefault));
+            }
+            cursor.skip_whitespace();
+            let value_pos = cursor.pos;
+            let value = cursor.number();
+            match key.to_ascii_lowercase() {
+                b'd' => {
+                    duration = value
+                        .and_then(valid_duration)
+                        .ok_or_else(|| cursor.error_at(value_pos, ErrorKind::InvalidDuration))?;
+                }
+                b'o' => {
+                    octave = value
+                        .filter(|&octave| octave < 10)
+                        .ok_or_else(|| cursor.error_at(value_pos, ErrorKind::InvalidOctave))?
+                        as u8;
+                }
+                b'b' => {
+                    tempo = value
+                        .filter(|tempo| (1..=MAX_TEMPO).contains(tempo))
+                        .ok_or_else(|| cursor.error_at(value_pos, ErrorKind::InvalidTempo))?
+                        as u16;
+                }
+                _ => return Err(cursor.error_at(key_pos, ErrorKind::UnknownDefault)),
+            }
+            cursor.skip_whitespace();
+            match cursor.peek() {
+                Some(b',') => cursor.pos += 1,
+                Some(b':') => (),
+                None => return Err(cursor.error(ErrorKind::MissingSection)),
+                Some(_) => return Err(cursor.error(ErrorKind::UnexpectedCharacter)),
+            }
+        }
+
+        let notes = Notes {
+            cursor,
+            duration,
+            octave,
+        };
+        // Goes through the notes once so that the iterator never meets an error
+        let mut check = notes.clone();
+        while check.next_note()?.is_some() {}
+
+        Ok(Self {
+            name: text[..name_end].trim(),
+            tempo,
+            notes,
+        })
+    }
+
+    /// Timing of the notes, for `Song::calc_note_duration`.
+    pub fn song(&self) -> Song {
+        Song::new(self.tempo)
+    }
+
+    /// The notes as `(frequency, divider)`, a negative divider being a dotted note
+    /// and a `music::REST` frequency a pause.
+    pub fn notes(&self) -> Notes<'a> {
+        self.notes.clone()
+    }
+}
+
+/// Iterator over the notes of a `Ringtone`.
+#[derive(Debug, Clone)]
+pub struct Notes<'a> {
+    cursor: Cursor<'a>,
+    // Defaults of the ringtone
+    duration: u8,
+    octave: u8,
+}
+
+impl Notes<'_> {
+    fn next_note(&mut self) -> Result<Option<(f64, i16)>, Error> {
+        let cursor = &mut self.cursor;
+        cursor.skip_whitespace();
+        if cursor.peek().is_none() {
+            return Ok(None);
+        }
+
+        let start = cursor.pos;
+        let duration = match cursor.number() {
+            Some(value) => valid_duration(value)
+                .ok_or_else(|| cursor.error_at(start, ErrorKind::InvalidDuration))?,
+            None => self.duration,
+        };
+        let semitone = match cursor.peek().map(|letter| letter.to_ascii_lowercase()) {
+            Some(b'c') => Some(0),
+            Some(b'd') => Some(2),
+            Some(b'e') => Some(4),
+            Some(b'f') => Some(5),
+            Some(b'g') => Some(7),
+            Some(b'a') => Some(9),
+            Some(b'b' | b'h') => Some(11),
+            Some(b'p') => None,
+            _ => return Err(cursor.error(ErrorKind::InvalidNote)),
+        };
+        cursor.pos += 1;
+        let sharp = cursor.eat(b'#');
+        let mut dotted = cursor.eat(b'.');
+        let octave_pos = cursor.pos;
+        let octave = match cursor.number() {
+            Some(octave) if octave < 10 => octave as u8,
+            Some(_) => return Err(cursor.error_at(octave_pos, ErrorKind::InvalidOctave)),
+            None => self.octave,
+        };
+        dotted |= cursor.eat(b'.');
+
+        cursor.skip_whitespace();
+        match cursor.peek() {
+            None => (),
+            Some(b',') => {
+                cursor.pos += 1;
+                cursor.skip_whitespace();
+                if cursor.peek().is_none() {
+                    return Err(cursor.error(ErrorKind::InvalidNote));
+                }
+            }
+            Some(_) => return Err(cursor.error(ErrorKind::UnexpectedCharacter)),
+        }
+
+        let frequency = match semitone {
+            None => music::REST,
+            Some(semitone) => {
+                // A sharp B is the C of the next octave
+                let note = octave * 12 + semitone + u8::from(sharp);
+                music::note_frequency(note / 12, note % 12)
+                    .ok_or_else(|| cursor.error_at(start, ErrorKind::NoteOutOfRange))?
+            }
+        };
+        let divider = i16::from(duration);
+        Ok(Some((frequency, if dotted { -divider } else { divider })))
+    }
+}
+
+impl Iterator for Notes<'_> {
+    type Item = (f64, i16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Checked by `Ringtone::parse`
+        self.next_note().ok().flatten()
+    }
+}
+
+fn valid_duration(value: u32) -> Option<u8> {
+    matches!(value, 1 | 2 | 4 | 8 | 16 | 32).then_some(value as u8)
+}
+
+// Position in the text of the ringtone
+#[derive(Debug, Clone)]
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    // Digits at the cursor, `None` when there are none
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        let mut value: u32 = 0;
+        while let Some(digit) = self.peek().filter(u8::is_ascii_digit) {
+            value = value
+                .saturating_mul(10)
+                .saturating_add(u32::from(digit - b'0'));
+            self.pos += 1;
+        }
+        (self.pos > start).then_some(value)
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, pos: usize, kind: ErrorKind) -> Error {
+        let before = &self.text[..pos];
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        Error {
+            line: before.matches('\n').count() as u32 + 1,
+            column: before[line_start..].chars().count() as u32 + 1,
+            kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::*;
+
+    const NOKIA: &str = "Nokia:d=4,o=5,b=225:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a";
+
+    fn notes(text: &str) -> Vec<(f64, i16)> {
+        Ringtone::parse(text).unwrap().notes().collect()
+    }
+
+    fn error(text: &str) -> (u32, u32, ErrorKind) {
+        let err = Ringtone::parse(text).unwrap_err();
+        (err.line, err.column, err.kind)
+    }
+
+    #[test]
+    fn parses_a_ringtone() {
+        let ringtone = Ringtone::parse(NOKIA).unwrap();
+        assert_eq!(ringtone.name, "Nokia");
+        assert_eq!(ringtone.tempo, 225);
+        let notes: Vec<_> = ringtone.notes().collect();
+        assert_eq!(notes.len(), 13);
+        assert_eq!(notes[..3], [(NOTE_E6, 8), (NOTE_D6, 8), (NOTE_FS5, 4)]);
+        assert_eq!(notes[12], (NOTE_A5, 2));
+    }
+
+    #[test]
+    fn missing_defaults_use_the_specification_ones() {
+        let ringtone = Ringtone::parse("Empty::c,p").unwrap();
+        assert_eq!(ringtone.tempo, 63);
+        assert_eq!(
+            ringtone.notes().collect::<Vec<_>>(),
+            [(NOTE_C6, 4), (REST, 4)]
+        );
+    }
+
+    #[test]
+    fn dots_sharps_and_whitespace() {
+        assert_eq!(
+            notes("Dots:d=8,o=5,b=100:c.,16c.6,2p.,e#"),
+            [(NOTE_C5, -8), (NOTE_C6, -16), (REST, -2), (NOTE_F5, 8)]
+        );
+        // B is also written H, a sharp B is the C above
+        assert_eq!(notes("B:o=4:h,b#"), [(NOTE_B4, 4), (NOTE_C5, 4)]);
+        assert_eq!(
+            notes(" Spaced : d = 2 , b = 90 :\n  c ,\n  D5\n"),
+            [(NOTE_C6, 2), (NOTE_D5, 2)]
+        );
+    }
+
+    #[test]
+    fn durations_follow_the_tempo() {
+        let ringtone = Ringtone::parse("Beat:b=120:8c,8c.").unwrap();
+        let song = ringtone.song();
+        let durations: Vec<_> = ringtone
+            .notes()
+            .map(|(_, divider)| song.calc_note_duration(divider))
+            .collect();
+        assert_eq!(durations, [250, 375]);
+    }
+
+    #[test]
+    fn bundled_ringtones_parse() {
+        for text in [crate::ringtones::NOKIA, crate::ringtones::TETRIS] {
+            let ringtone = Ringtone::parse(text).unwrap();
+            assert!(ringtone.notes().count() > 0, "{}", ringtone.name);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_line_and_column() {
+        use ErrorKind::*;
+
+        assert_eq!(error("No sections"), (1, 12, MissingSection));
+        assert_eq!(error("t:d=4"), (1, 6, MissingSection));
+        assert_eq!(error("t:x=3:c"), (1, 3, UnknownDefault));
+        assert_eq!(error("t:d=3:c"), (1, 5, InvalidDuration));
+        assert_eq!(error("t:o=12:c"), (1, 5, InvalidOctave));
+        assert_eq!(error("t:b=0:c"), (1, 5, InvalidTempo));
+        assert_eq!(error("t:d=4;o=5:c"), (1, 6, UnexpectedCharacter));
+        assert_eq!(error("t::c,\n  8k"), (2, 4, InvalidNote));
+        assert_eq!(error("t::c,e,"), (1, 8, InvalidNote));
+        assert_eq!(error("t::c;e"), (1, 5, UnexpectedCharacter));
+        assert_eq!(error("t::3c"), (1, 4, InvalidDuration));
+        assert_eq!(error("t::c12"), (1, 5, InvalidOctave));
+        assert_eq!(error("t::c,\n\t16a9"), (2, 2, NoteOutOfRange));
+    }
+
+    #[test]
+    fn errors_are_displayed_with_their_position() {
+        let err = Ringtone::parse("Song:d=4:c,\nx").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 1: invalid note");
+    }
+}
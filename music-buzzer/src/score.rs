@@ -0,0 +1,194 @@
+//! Melodies written with `Pitch` and `Duration`, built in a `const`:
+//!
+//! ```
+//! use music_buzzer::duration::Duration;
+//! use music_buzzer::pitch::{Pitch, PitchClass::*};
+//! use music_buzzer::score::Score;
+//!
+//! static INTRO: Score<4> = Score::new(120)
+//!     .note(Pitch::new(E, 5), Duration::QUARTER)
+//!     .rest(Duration::EIGHTH)
+//!     .tempo(90)
+//!     .note(Pitch::new(G, 5), Duration::HALF.dotted())
+//!     .transpose(-2);
+//! ```
+//!
+//! The capacity `N` is checked when the score is built, a score with more events
+//! doesn't compile.
+
+use crate::duration::Duration;
+use crate::pitch::Pitch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Note(Pitch, Duration),
+    Rest(Duration),
+    /// Quarter notes per minute from there on.
+    Tempo(u16),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Score<const N: usize> {
+    tempo: u16,
+    events: [Event; N],
+    len: usize,
+}
+
+impl<const N: usize> Score<N> {
+    /// An empty score starting at `tempo` quarter notes per minute.
+    pub const fn new(tempo: u16) -> Self {
+        assert!(tempo > 0, "the tempo must be above 0");
+        Self {
+            tempo,
+            events: [Event::Rest(Duration::WHOLE); N],
+            len: 0,
+        }
+    }
+
+    pub const fn note(self, pitch: Pitch, duration: Duration) -> Self {
+        self.push(Event::Note(pitch, duration))
+    }
+
+    pub const fn rest(self, duration: Duration) -> Self {
+        self.push(Event::Rest(duration))
+    }
+
+    /// Changes the tempo for the events after it.
+    pub const fn tempo(self, tempo: u16) -> Self {
+        assert!(tempo > 0, "the tempo must be above 0");
+        self.push(Event::Tempo(tempo))
+    }
+
+    /// Holds the last note or rest for `duration` longer.
+    pub const fn tie(mut self, duration: Duration) -> Self {
+        assert!(self.len > 0, "nothing to tie to");
+        self.events[self.len - 1] = match self.events[self.len - 1] {
+            Event::Note(pitch, held) => Event::Note(pitch, held.tie(duration)),
+            Event::Rest(held) => Event::Rest(held.tie(duration)),
+            Event::Tempo(_) => panic!("a tempo change can't be tied"),
+        };
+        self
+    }
+
+    /// Moves all the notes up by `semitones`, or down when negative.
+    pub const fn transpose(mut self, semitones: i16) -> Self {
+        let mut idx = 0;
+        while idx < self.len {
+            if let Event::Note(pitch, duration) = self.events[idx] {
+                self.events[idx] = Event::Note(pitch.transpose(semitones), duration);
+            }
+            idx += 1;
+        }
+        self
+    }
+
+    pub const fn shift_octaves(self, octaves: i8) -> Self {
+        self.transpose(octaves as i16 * 12)
+    }
+
+    /// Tempo at the start of the score.
+    pub const fn initial_tempo(&self) -> u16 {
+        self.tempo
+    }
+
+    pub const fn events(&self) -> &[Event] {
+        self.events.split_at(self.len).0
+    }
+
+    /// Length of the whole score in milliseconds, following its tempo changes.
+    pub fn millis(&self) -> u32 {
+        let mut tempo = self.tempo;
+        let mut total = 0;
+        for event in self.events() {
+            match *event {
+                Event::Note(_, duration) | Event::Rest(duration) => total += duration.millis(tempo),
+                Event::Tempo(changed) => tempo = changed,
+            }
+        }
+        total
+    }
+
+    const fn push(mut self, event: Event) -> Self {
+        assert!(self.len < N, "more events than the capacity of the score");
+        self.events[self.len] = event;
+        self.len += 1;
+        self
+    }
+}
+
+/// Two scores played together on two buzzers, a melody and its bass line.
+///
+/// The parts share their timing: a tempo change in either of them applies to
+/// both, from where it is in the tune.
+#[derive(Debug, Clone, Copy)]
+pub struct Duet<const M: usize, const B: usize> {
+    pub melody: Score<M>,
+    pub bass: Score<B>,
+}
+
+impl<const M: usize, const B: usize> Duet<M, B> {
+    pub const fn new(melody: Score<M>, bass: Score<B>) -> Self {
+        assert!(
+            melody.initial_tempo() == bass.initial_tempo(),
+            "the parts of a duet must start at the same tempo"
+        );
+        Self { melody, bass }
+    }
+
+    /// Tempo at the start of both parts.
+    pub const fn initial_tempo(&self) -> u16 {
+        self.melody.initial_tempo()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pitch::PitchClass::*;
+
+    static SCALE: Score<5> = Score::new(120)
+        .note(Pitch::new(C, 4), Duration::QUARTER)
+        .note(Pitch::new(D, 4), Duration::QUARTER)
+        .tempo(60)
+        .note(Pitch::new(E, 4), Duration::EIGHTH)
+        .tie(Duration::EIGHTH)
+        .rest(Duration::QUARTER);
+
+    #[test]
+    fn builds_the_events_in_order() {
+        assert_eq!(SCALE.initial_tempo(), 120);
+        assert_eq!(
+            SCALE.events(),
+            [
+                Event::Note(Pitch::new(C, 4), Duration::QUARTER),
+                Event::Note(Pitch::new(D, 4), Duration::QUARTER),
+                Event::Tempo(60),
+                Event::Note(Pitch::new(E, 4), Duration::QUARTER),
+                Event::Rest(Duration::QUARTER),
+            ]
+        );
+    }
+
+    #[test]
+    fn length_follows_the_tempo_changes() {
+        // Two quarter notes at 120, two at 60
+        assert_eq!(SCALE.millis(), 2 * 500 + 2 * 1000);
+    }
+
+    #[test]
+    fn transposes_the_notes_only() {
+        let up = SCALE.shift_octaves(1).transpose(-1);
+        assert_eq!(
+            up.events()[0],
+            Event::Note(Pitch::new(B, 4), Duration::QUARTER)
+        );
+        assert_eq!(
+            up.events()[2..],
+            [
+                Event::Tempo(60),
+                Event::Note(Pitch::new(Ds, 5), Duration::QUARTER),
+                Event::Rest(Duration::QUARTER),
+            ]
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Songs imported from the MIDI files of `songs/` by `build.rs`, one module with
+//! a `TEMPO` and a `MELODY` per file.
+
+include!(concat!(env!("OUT_DIR"), "/songs.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::*;
+
+    #[test]
+    fn highest_note_skips_the_bass_and_the_percussion() {
+        assert_eq!(ode_to_joy::TEMPO, 100);
+        assert_eq!(
+            ode_to_joy::MELODY[..4],
+            [(NOTE_E5, 4), (NOTE_E5, 4), (NOTE_F5, 4), (NOTE_G5, 4)]
+        );
+        // The dotted quarter and eighth note at the end of the first line
+        assert_eq!(
+            ode_to_joy::MELODY[12..15],
+            [(NOTE_E5, -4), (NOTE_D5, 8), (NOTE_D5, 2)]
+        );
+        assert_eq!(ode_to_joy::MELODY.len(), 30);
+    }
+
+    #[test]
+    fn chosen_channel_is_played_alone() {
+        assert_eq!(twinkle::TEMPO, 90);
+        assert_eq!(
+            twinkle::MELODY[..7],
+            [
+                (NOTE_C5, 4),
+                (NOTE_C5, 4),
+                (NOTE_G5, 4),
+                (NOTE_G5, 4),
+                (NOTE_A5, 4),
+                (NOTE_A5, 4),
+                (NOTE_G5, 2),
+            ]
+        );
+        assert!(twinkle::MELODY.iter().all(|&(note, _)| note != NOTE_C6));
+    }
+}
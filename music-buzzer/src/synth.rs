@@ -0,0 +1,297 @@
+//! Wavetable synthesizer, to play the tunes on a speaker instead of the buzzers.
+//!
+//! `Synth` plays the notes of a `Player` as 16-bit samples at a fixed rate, with
+//! a sine, triangle, sawtooth or square voice and the envelope and volume of the
+//! `Voice`. The parts of a duet are mixed. Commands go through `Synth::handle`
+//! rather than straight to the player, so that a note cut short starts over.
+//!
+//! With the `dac` feature the firmware plays the samples on the 8-bit DAC of the
+//! ESP32 on GPIO25, through `dac_level`. With the `i2s` feature it streams them
+//! by DMA to an external I2S DAC or amplifier. See `player_task` in `main.rs`.
+
+use crate::player::{Command, Note, Player, Status, PARTS};
+use crate::render::{self, AMPLITUDE};
+use crate::voice::{Voice, FULL_DUTY};
+
+include!(concat!(env!("OUT_DIR"), "/wavetable.rs"));
+
+// Phases of a period, the `u32` range
+const PHASES: f64 = 4_294_967_296.0;
+
+/// Shape of a period of the voice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Sawtooth,
+    /// The buzzer sound, without its harshness once smoothed by the speaker.
+    Square,
+}
+
+impl Waveform {
+    /// Sample at `phase`, a whole period being the `u32` range. All the shapes
+    /// start at 0 going up, and peak at `i16::MAX`.
+    pub fn sample(self, phase: u32) -> i16 {
+        let phase = (phase >> 16) as u16;
+        match self {
+            Waveform::Sine => SINE[usize::from(phase >> 8)],
+            Waveform::Triangle => {
+                // Up over the first and last quarters, down in between
+                let shifted = phase.wrapping_add(1 << 14);
+                let ramp = if shifted < 1 << 15 {
+                    shifted
+                } else {
+                    u16::MAX - shifted
+                };
+                (i32::from(ramp) * 2 - (1 << 15)).max(-i32::from(i16::MAX)) as i16
+            }
+            Waveform::Sawtooth => (phase as i16).max(-i16::MAX),
+            Waveform::Square if phase < 1 << 15 => i16::MAX,
+            Waveform::Square => -i16::MAX,
+        }
+    }
+}
+
+/// Level of an 8-bit DAC for `sample`, silence being the middle of the range.
+pub fn dac_level(sample: i16) -> u8 {
+    ((i32::from(sample) - i32::from(i16::MIN)) >> 8) as u8
+}
+
+// What plays until the next change of the player
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    notes: [Option<Note>; PARTS],
+    elapsed_ms: [Option<u32>; PARTS],
+    // Phase increments of the notes for each sample
+    phase_steps: [u32; PARTS],
+    samples: u32,
+}
+
+#[derive(Debug)]
+pub struct Synth {
+    sample_rate: u32,
+    pub waveform: Waveform,
+    // Phases of the parts, kept from one note to the next so that they don't click
+    phases: [u32; PARTS],
+    segment: Option<Segment>,
+    // Samples of `segment` already given
+    played: u32,
+}
+
+impl Synth {
+    pub fn new(sample_rate: u32, waveform: Waveform) -> Self {
+        Self {
+            sample_rate,
+            waveform,
+            phases: [0; PARTS],
+            segment: None,
+            played: 0,
+        }
+    }
+
+    /// Hands `command` to `player`, like the buzzer task does.
+    pub fn handle(&mut self, player: &mut Player, command: Command) -> Option<Status> {
+        if command.interrupts_note() {
+            self.played = 0;
+        }
+        let status = player.handle(command);
+        self.refresh(player);
+        status
+    }
+
+    /// Fills `samples` with what `player` plays next, moving it on at the end of
+    /// the notes and passing the `Status` it reports to `report`. Silent while
+    /// idle or paused.
+    pub fn fill(
+        &mut self,
+        player: &mut Player,
+        samples: &mut [i16],
+        mut report: impl FnMut(Status),
+    ) {
+        for sample in samples {
+            while self
+                .segment
+                .is_some_and(|segment| self.played >= segment.samples)
+            {
+                self.played = 0;
+                if let Some(status) = player.advance() {
+                    report(status);
+                }
+                self.refresh(player);
+            }
+            *sample = match self.segment {
+                Some(segment) => {
+                    let mixed = self.mix(player.voice(), &segment);
+                    self.played += 1;
+                    mixed
+                }
+                None => 0,
+            };
+        }
+    }
+
+    fn refresh(&mut self, player: &Player) {
+        let sample_rate = f64::from(self.sample_rate);
+        self.segment = player.next_change_ms().map(|change_ms| {
+            let notes = player.notes();
+            Segment {
+                notes,
+                elapsed_ms: core::array::from_fn(|part| player.elapsed_ms(part)),
+                phase_steps: notes.map(|note| {
+                    note.map_or(0, |note| (note.frequency * PHASES / sample_rate) as u32)
+                }),
+                samples: render::sample_count(change_ms, self.sample_rate),
+            }
+        });
+    }
+
+    // Next sample of the notes of `segment`, each part at the level of its
+    // envelope
+    fn mix(&mut self, voice: Voice, segment: &Segment) -> i16 {
+        let since_ms = (u64::from(self.played) * 1000 / u64::from(self.sample_rate)) as u32;
+        let mut mixed = 0;
+        for part in 0..PARTS {
+            let (Some(note), Some(elapsed_ms)) = (segment.notes[part], segment.elapsed_ms[part])
+            else {
+                continue;
+            };
+            if note.is_rest() {
+                continue;
+            }
+            self.phases[part] = self.phases[part].wrapping_add(segment.phase_steps[part]);
+            let wave = i64::from(self.waveform.sample(self.phases[part]));
+            // The duty of the buzzer is the level, full at `FULL_DUTY`
+            let duty = i64::from(voice.duty(elapsed_ms + since_ms, note.on_ms));
+            mixed +=
+                wave * duty * i64::from(AMPLITUDE) / (i64::from(FULL_DUTY) * i64::from(i16::MAX));
+        }
+        mixed.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duets;
+    use crate::music::*;
+    use crate::player::Tune;
+
+    const SAMPLE_RATE: u32 = 8000;
+    // A quarter note of A4 then an eighth note rest, 400 and 200 ms
+    const MELODY: [(f64, i16); 2] = [(NOTE_A4, 4), (REST, 8)];
+
+    fn play(tune: Tune) -> (Synth, Player) {
+        let mut synth = Synth::new(SAMPLE_RATE, Waveform::Sine);
+        let mut player = Player::new();
+        synth.handle(&mut player, Command::SetEnvelope(Voice::FLAT.envelope));
+        synth.handle(&mut player, Command::Play(tune));
+        (synth, player)
+    }
+
+    fn melody() -> Tune {
+        Tune::Melody {
+            notes: &MELODY,
+            tempo: 150,
+        }
+    }
+
+    #[test]
+    fn waveforms_start_at_zero_and_peak_at_a_quarter() {
+        let quarter = 1 << 30;
+        for waveform in [Waveform::Sine, Waveform::Triangle, Waveform::Sawtooth] {
+            assert_eq!(waveform.sample(0), 0, "{waveform:?}");
+            assert!(waveform.sample(quarter) > 0, "{waveform:?}");
+        }
+        assert_eq!(Waveform::Sine.sample(quarter), i16::MAX);
+        assert_eq!(Waveform::Sine.sample(3 * quarter), -i16::MAX);
+        assert_eq!(Waveform::Triangle.sample(quarter), i16::MAX - 1);
+        assert_eq!(Waveform::Triangle.sample(3 * quarter), -i16::MAX);
+        assert_eq!(Waveform::Sawtooth.sample(2 * quarter - 1), i16::MAX);
+        assert_eq!(Waveform::Sawtooth.sample(2 * quarter), -i16::MAX);
+        assert_eq!(Waveform::Square.sample(0), i16::MAX);
+        assert_eq!(Waveform::Square.sample(u32::MAX), -i16::MAX);
+    }
+
+    #[test]
+    fn plays_the_notes_for_their_length() {
+        let (mut synth, mut player) = play(melody());
+        let mut samples = [0; 6000];
+        let mut statuses = Vec::new();
+        let mut filled = 0;
+        for chunk in samples.chunks_mut(256) {
+            synth.fill(&mut player, chunk, |status| statuses.push((filled, status)));
+            filled += chunk.len();
+        }
+
+        // 360 ms of A4, then the gap, the rest and silence once finished
+        let peak = samples[..2880].iter().copied().max().unwrap();
+        assert!(peak > AMPLITUDE - AMPLITUDE / 100 && peak <= AMPLITUDE);
+        assert!(samples[2880..].iter().all(|&sample| sample == 0));
+        // Sign changes twice a period: 440 Hz over 360 ms
+        let crossings = samples[..2880]
+            .windows(2)
+            .filter(|pair| (pair[0] < 0) != (pair[1] < 0))
+            .count();
+        assert!((315..=318).contains(&crossings), "{crossings}");
+
+        let at = |status| {
+            statuses
+                .iter()
+                .find(|&&(_, found)| found == status)
+                .unwrap()
+                .0
+        };
+        assert_eq!(
+            at(Status::Progress {
+                played: 1,
+                notes: 2,
+                pitch: None
+            }),
+            3072
+        );
+        assert_eq!(at(Status::Finished), 4608);
+        assert!(!player.is_playing());
+    }
+
+    #[test]
+    fn commands_go_through_the_synth() {
+        let (mut synth, mut player) = play(melody());
+        let mut samples = [0; 800];
+        synth.fill(&mut player, &mut samples, |_| ());
+        assert_eq!(
+            synth.handle(&mut player, Command::Pause),
+            Some(Status::Paused)
+        );
+        synth.fill(&mut player, &mut samples, |_| ());
+        assert!(samples.iter().all(|&sample| sample == 0));
+
+        // Played again from its start, the whole note is still to come
+        synth.handle(&mut player, Command::Resume);
+        synth.handle(&mut player, Command::SetVolume(50));
+        let mut statuses = Vec::new();
+        let mut note = [0; 3199];
+        synth.fill(&mut player, &mut note, |status| statuses.push(status));
+        assert!(statuses.is_empty());
+        let peak = note.iter().copied().max().unwrap();
+        assert!(peak > AMPLITUDE / 2 - AMPLITUDE / 200 && peak <= AMPLITUDE / 2);
+    }
+
+    #[test]
+    fn duet_parts_are_mixed() {
+        let (mut synth, mut player) = play(Tune::from(&duets::TWINKLE));
+        synth.waveform = Waveform::Square;
+        let mut samples = [0; 800];
+        synth.fill(&mut player, &mut samples, |_| ());
+        // Both parts high or low at once, the bass is an octave and a half below
+        assert_eq!(samples.iter().max(), Some(&(2 * AMPLITUDE)));
+        assert_eq!(samples.iter().min(), Some(&(-2 * AMPLITUDE)));
+    }
+
+    #[test]
+    fn dac_levels_cover_the_range() {
+        assert_eq!(dac_level(i16::MIN), 0);
+        assert_eq!(dac_level(0), 128);
+        assert_eq!(dac_level(i16::MAX), 255);
+    }
+}
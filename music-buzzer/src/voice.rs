@@ -0,0 +1,182 @@
+//! How the notes sound on the buzzer.
+//!
+//! A passive buzzer is loudest with a 50% duty square wave and gets quieter as
+//! the duty goes down, so the volume and the envelope of a note are both played
+//! by stepping the duty of the LEDC channel, every `STEP_MS` while the note
+//! sounds.
+
+/// Duty counts of the LEDC timer, configured with `Duty10Bit`.
+pub const DUTY_RANGE: u32 = 1 << 10;
+/// Duty of a note at full level and volume, the 50% of a square wave.
+pub const FULL_DUTY: u32 = DUTY_RANGE / 2;
+/// Time between two duty changes of a note.
+pub const STEP_MS: u32 = 5;
+
+/// Attack, decay, sustain and release of a note. Levels are percents of the
+/// peak reached at the end of the attack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    /// Rise from silence to the peak.
+    pub attack_ms: u32,
+    /// Fall from the peak to the sustain level.
+    pub decay_ms: u32,
+    pub sustain: u8,
+    /// Fall to silence once the note is over, in the silence after it.
+    pub release_ms: u32,
+}
+
+impl Envelope {
+    /// The whole note at full level, the plain square wave.
+    pub const FLAT: Envelope = Envelope {
+        attack_ms: 0,
+        decay_ms: 0,
+        sustain: 100,
+        release_ms: 0,
+    };
+
+    /// A short attack and a decay to a softer sustain, like a plucked string.
+    pub const PLUCK: Envelope = Envelope {
+        attack_ms: 10,
+        decay_ms: 80,
+        sustain: 60,
+        release_ms: 40,
+    };
+
+    /// Level in percent `elapsed_ms` after the start of a note sounding for
+    /// `on_ms`.
+    pub fn level(&self, elapsed_ms: u32, on_ms: u32) -> u8 {
+        if elapsed_ms < on_ms {
+            return self.held_level(elapsed_ms);
+        }
+        // Released from wherever the note got to, even mid-attack
+        let released_ms = elapsed_ms - on_ms;
+        if released_ms >= self.release_ms {
+            return 0;
+        }
+        let level = u32::from(self.held_level(on_ms));
+        (level * (self.release_ms - released_ms) / self.release_ms) as u8
+    }
+
+    fn held_level(&self, elapsed_ms: u32) -> u8 {
+        let sustain = u32::from(self.sustain.min(100));
+        if elapsed_ms < self.attack_ms {
+            (100 * elapsed_ms / self.attack_ms) as u8
+        } else if elapsed_ms - self.attack_ms < self.decay_ms {
+            let decayed_ms = elapsed_ms - self.attack_ms;
+            (100 - (100 - sustain) * decayed_ms / self.decay_ms) as u8
+        } else {
+            sustain as u8
+        }
+    }
+}
+
+/// How long notes are held, the rest of their duration is silent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Articulation {
+    /// Notes are held for half of their duration.
+    Staccato,
+    /// 10% of silence after the notes, so that repeated notes can be told apart.
+    #[default]
+    Normal,
+    /// Notes are held until the next one.
+    Legato,
+}
+
+impl Articulation {
+    /// Silence after a note lasting `duration_ms`.
+    pub fn gap_ms(self, duration_ms: u32) -> u32 {
+        match self {
+            Articulation::Staccato => duration_ms / 2,
+            Articulation::Normal => duration_ms / 10,
+            Articulation::Legato => 0,
+        }
+    }
+}
+
+/// Settings the notes are played with, kept from one tune to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Voice {
+    pub envelope: Envelope,
+    pub articulation: Articulation,
+    /// Percent of the full duty, from 0 to 100.
+    pub volume: u8,
+}
+
+impl Default for Voice {
+    fn default() -> Self {
+        Self {
+            envelope: Envelope::PLUCK,
+            articulation: Articulation::Normal,
+            volume: 100,
+        }
+    }
+}
+
+impl Voice {
+    /// Every note at 50% duty for 90% of its duration.
+    pub const FLAT: Voice = Voice {
+        envelope: Envelope::FLAT,
+        articulation: Articulation::Normal,
+        volume: 100,
+    };
+
+    /// Duty counts `elapsed_ms` after the start of a note sounding for `on_ms`.
+    ///
+    /// The fundamental of the square wave grows about linearly with the duty up
+    /// to 50%, so the level and the volume scale it linearly too.
+    pub fn duty(&self, elapsed_ms: u32, on_ms: u32) -> u32 {
+        let level = u32::from(self.envelope.level(elapsed_ms, on_ms));
+        let volume = u32::from(self.volume.min(100));
+        FULL_DUTY * level * volume / (100 * 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_goes_through_its_stages() {
+        let envelope = Envelope {
+            attack_ms: 10,
+            decay_ms: 20,
+            sustain: 50,
+            release_ms: 40,
+        };
+        let levels: Vec<_> = [0, 5, 10, 20, 30, 100, 199, 200, 220, 240]
+            .into_iter()
+            .map(|elapsed_ms| envelope.level(elapsed_ms, 200))
+            .collect();
+        assert_eq!(levels, [0, 50, 100, 75, 50, 50, 50, 50, 25, 0]);
+
+        // A note shorter than the attack is released from where it got to
+        assert_eq!(envelope.level(4, 4), 40);
+        assert_eq!(envelope.level(24, 4), 20);
+        assert_eq!(Envelope::FLAT.level(0, 200), 100);
+        assert_eq!(Envelope::FLAT.level(200, 200), 0);
+    }
+
+    #[test]
+    fn duty_follows_the_level_and_the_volume() {
+        assert_eq!(Voice::FLAT.duty(0, 100), FULL_DUTY);
+        assert_eq!(Voice::FLAT.duty(100, 100), 0);
+        let quiet = Voice {
+            volume: 50,
+            ..Voice::FLAT
+        };
+        assert_eq!(quiet.duty(0, 100), FULL_DUTY / 2);
+        let voice = Voice {
+            volume: 50,
+            ..Voice::default()
+        };
+        // Sustained at 60% of half the volume
+        assert_eq!(voice.duty(500, 1000), FULL_DUTY * 30 / 100);
+    }
+
+    #[test]
+    fn articulation_sets_the_gap() {
+        assert_eq!(Articulation::Staccato.gap_ms(400), 200);
+        assert_eq!(Articulation::Normal.gap_ms(400), 40);
+        assert_eq!(Articulation::Legato.gap_ms(400), 0);
+    }
+}
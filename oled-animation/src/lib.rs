@@ -0,0 +1,90 @@
+//! Frame by frame animations for the OLED displays.
+//!
+//! `Animation` only picks the frame to show, drawing it is up to the caller, so it
+//! works with any image type: the `ImageRaw` frames of a sprite sheet, glyphs, or
+//! whole screens.
+#![cfg_attr(not(test), no_std)]
+
+/// Frames shown one after the other in a loop, each for `frame_ticks` updates.
+///
+/// The frames can be anything, e.g. the `ImageRaw` frames of a sprite sheet split
+/// by `build.rs`. Call `tick` once per update and draw `frame`:
+///
+/// ```ignore
+/// let mut legs = Animation::new(&RAW_TREX_RUN, 2);
+/// if legs.tick() {
+///     Image::new(legs.frame(), position).draw(&mut display)?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Animation<'a, T> {
+    frames: &'a [T],
+    frame_ticks: u32,
+    frame: usize,
+    // Updates the current frame has been shown
+    ticks: u32,
+}
+
+impl<'a, T> Animation<'a, T> {
+    /// Starts on the first of `frames`, there must be at least one.
+    pub const fn new(frames: &'a [T], frame_ticks: u32) -> Self {
+        assert!(!frames.is_empty());
+        Self {
+            frames,
+            frame_ticks,
+            frame: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Advances by one update. Returns `true` when the next frame is shown.
+    pub fn tick(&mut self) -> bool {
+        self.ticks += 1;
+        if self.ticks < self.frame_ticks || self.frames.len() == 1 {
+            return false;
+        }
+        self.ticks = 0;
+        self.frame = (self.frame + 1) % self.frames.len();
+        true
+    }
+
+    /// Goes back to the first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.ticks = 0;
+    }
+
+    pub fn frame(&self) -> &'a T {
+        &self.frames[self.frame]
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_loop_at_their_rate() {
+        let mut animation = Animation::new(&['a', 'b', 'c'], 2);
+        let mut shown = [' '; 7];
+        for frame in &mut shown {
+            *frame = *animation.frame();
+            animation.tick();
+        }
+        assert_eq!(shown, ['a', 'a', 'b', 'b', 'c', 'c', 'a']);
+
+        animation.restart();
+        assert_eq!(animation.frame_index(), 0);
+    }
+
+    #[test]
+    fn single_frame_never_changes() {
+        let mut animation = Animation::new(&[1], 1);
+        assert!((0..5).all(|_| !animation.tick()));
+        assert_eq!(animation.frame(), &1);
+    }
+}
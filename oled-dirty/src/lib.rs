@@ -0,0 +1,342 @@
+//! Partial updates for SSD1306 displays.
+//!
+//! The SSD1306 memory is split in pages of 8 pixel rows, one byte per column. Sending
+//! the full 128x64 buffer over I2C takes most of a frame, so `DirtyPages` remembers
+//! the columns changed on each page and only those get sent, from a `PageBuffer` kept
+//! in the same layout as the display memory:
+//!
+//! ```ignore
+//! for span in dirty.take().spans() {
+//!     let (start, end) = span.draw_area();
+//!     display.set_draw_area(start, end)?;
+//!     display.draw(buffer.span_data(&span))?;
+//! }
+//! ```
+#![cfg_attr(not(test), no_std)]
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use heapless::Vec;
+
+/// Changed columns of a single page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSpan {
+    pub page: u8,
+    pub first_column: u8,
+    pub last_column: u8,
+}
+
+impl PageSpan {
+    /// Area to pass to `Ssd1306::set_draw_area`, in pixels, the end being exclusive.
+    pub fn draw_area(&self) -> ((u8, u8), (u8, u8)) {
+        let top = self.page * 8;
+        ((self.first_column, top), (self.last_column + 1, top + 8))
+    }
+
+    pub fn width(&self) -> usize {
+        usize::from(self.last_column - self.first_column) + 1
+    }
+}
+
+// Separate spans kept on a page, more are merged with their closest neighbour
+const SPANS_PER_PAGE: usize = 3;
+// Spans closer than this are merged, sending the columns in between costs less
+// than addressing a new area of the display
+const MIN_SPAN_GAP: u8 = 8;
+
+type Spans = Vec<(u8, u8), { SPANS_PER_PAGE + 1 }>;
+
+/// Tracks the changed columns of each of the `PAGES` pages of a display.
+///
+/// Areas are clipped to the display, which may be shorter than `PAGES` pages. Each page keeps up to `SPANS_PER_PAGE` column
+/// spans, so a sprite on the left doesn't make a page dirty up to one on the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirtyPages<const PAGES: usize> {
+    size: Size,
+    pages: [Spans; PAGES],
+}
+
+impl<const PAGES: usize> DirtyPages<PAGES> {
+    /// Creates an empty tracker for a display of `size`, 128 pixels wide at most.
+    pub const fn new(size: Size) -> Self {
+        Self {
+            size,
+            pages: [const { Vec::new() }; PAGES],
+        }
+    }
+
+    /// Marks the pixels of `area` as changed.
+    pub fn mark(&mut self, area: &Rectangle) {
+        let area = area.intersection(&self.screen());
+        let Some(bottom_right) = area.bottom_right() else {
+            return;
+        };
+        let (left, right) = (area.top_left.x as u8, bottom_right.x as u8);
+        for page in area.top_left.y / 8..=bottom_right.y / 8 {
+            add_span(&mut self.pages[page as usize], left, right);
+        }
+    }
+
+    /// Marks the whole display as changed.
+    pub fn mark_all(&mut self) {
+        self.clear();
+        self.mark(&self.screen());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.iter().all(|spans| spans.is_empty())
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.iter_mut().for_each(Vec::clear);
+    }
+
+    /// Returns the changed areas and starts tracking from scratch.
+    pub fn take(&mut self) -> Self {
+        let dirty = self.clone();
+        self.clear();
+        dirty
+    }
+
+    /// Changed columns, page by page from the top and from left to right on a page.
+    pub fn spans(&self) -> impl Iterator<Item = PageSpan> + '_ {
+        self.pages.iter().enumerate().flat_map(|(page, spans)| {
+            spans
+                .iter()
+                .map(move |&(first_column, last_column)| PageSpan {
+                    page: page as u8,
+                    first_column,
+                    last_column,
+                })
+        })
+    }
+
+    // Part of the display that is tracked
+    fn screen(&self) -> Rectangle {
+        let height = self.size.height.min(PAGES as u32 * 8);
+        Rectangle::new(Point::zero(), Size::new(self.size.width, height))
+    }
+
+    /// Number of bytes the spans take to send.
+    pub fn byte_count(&self) -> usize {
+        self.spans().map(|span| span.width()).sum()
+    }
+}
+
+// Inserts a span in the sorted spans of a page, merging the ones that get close
+fn add_span(spans: &mut Spans, left: u8, right: u8) {
+    let idx = spans
+        .iter()
+        .position(|&(first, _)| first > left)
+        .unwrap_or(spans.len());
+    // There is always room for one more than `SPANS_PER_PAGE`
+    spans.insert(idx, (left, right)).ok();
+
+    loop {
+        // Gap between each span and the next one, overlapping ones have none
+        let closest = spans
+            .windows(2)
+            .enumerate()
+            .map(|(idx, pair)| (idx, pair[1].0.saturating_sub(pair[0].1)))
+            .min_by_key(|&(_, gap)| gap);
+        match closest {
+            Some((idx, gap)) if gap <= MIN_SPAN_GAP || spans.len() > SPANS_PER_PAGE => {
+                let (_, last) = spans.remove(idx + 1);
+                spans[idx].1 = spans[idx].1.max(last);
+            }
+            _ => break,
+        }
+    }
+}
+
+/// Monochrome frame buffer in the SSD1306 memory layout.
+///
+/// Byte `x` of page `p` holds the pixels of column `x` from row `8 * p` to `8 * p + 7`,
+/// the least significant bit being the top one. Pixels outside of the buffer are
+/// ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageBuffer<const WIDTH: usize, const PAGES: usize> {
+    pages: [[u8; WIDTH]; PAGES],
+}
+
+impl<const WIDTH: usize, const PAGES: usize> Default for PageBuffer<WIDTH, PAGES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WIDTH: usize, const PAGES: usize> PageBuffer<WIDTH, PAGES> {
+    pub const fn new() -> Self {
+        Self {
+            pages: [[0; WIDTH]; PAGES],
+        }
+    }
+
+    pub fn pixel(&self, point: Point) -> bool {
+        match Self::index(point) {
+            Some((page, column, bit)) => self.pages[page][column] & (1 << bit) != 0,
+            None => false,
+        }
+    }
+
+    /// Number of pixels that are on.
+    pub fn count_on(&self) -> u32 {
+        self.pages
+            .iter()
+            .flatten()
+            .map(|byte| byte.count_ones())
+            .sum()
+    }
+
+    /// Bytes of the display memory covered by `span`.
+    pub fn span_data(&self, span: &PageSpan) -> &[u8] {
+        let first = usize::from(span.first_column);
+        &self.pages[usize::from(span.page)][first..first + span.width()]
+    }
+
+    fn index(point: Point) -> Option<(usize, usize, usize)> {
+        let (x, y) = (
+            usize::try_from(point.x).ok()?,
+            usize::try_from(point.y).ok()?,
+        );
+        (x < WIDTH && y < PAGES * 8).then_some((y / 8, x, y % 8))
+    }
+}
+
+impl<const WIDTH: usize, const PAGES: usize> OriginDimensions for PageBuffer<WIDTH, PAGES> {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, PAGES as u32 * 8)
+    }
+}
+
+impl<const WIDTH: usize, const PAGES: usize> DrawTarget for PageBuffer<WIDTH, PAGES> {
+    type Color = BinaryColor;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let Some((page, column, bit)) = Self::index(point) {
+                let byte = &mut self.pages[page][column];
+                match color {
+                    BinaryColor::On => *byte |= 1 << bit,
+                    BinaryColor::Off => *byte &= !(1 << bit),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let fill = match color {
+            BinaryColor::On => 0xFF,
+            BinaryColor::Off => 0x00,
+        };
+        self.pages = [[fill; WIDTH]; PAGES];
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn areas_are_clipped_and_split_in_pages() {
+        let mut dirty = DirtyPages::<8>::new(Size::new(128, 64));
+        assert!(dirty.is_empty());
+        dirty.mark(&Rectangle::new(Point::new(10, 6), Size::new(4, 4)));
+        dirty.mark(&Rectangle::new(Point::new(120, 9), Size::new(20, 1)));
+        dirty.mark(&Rectangle::new(Point::new(-5, -5), Size::new(3, 3)));
+        dirty.mark(&Rectangle::new(Point::new(3, 3), Size::zero()));
+
+        let spans: std::vec::Vec<_> = dirty.spans().collect();
+        assert_eq!(
+            spans,
+            [
+                PageSpan {
+                    page: 0,
+                    first_column: 10,
+                    last_column: 13,
+                },
+                PageSpan {
+                    page: 1,
+                    first_column: 10,
+                    last_column: 13,
+                },
+                PageSpan {
+                    page: 1,
+                    first_column: 120,
+                    last_column: 127,
+                },
+            ]
+        );
+        assert_eq!(dirty.byte_count(), 4 + 4 + 8);
+        assert_eq!(spans[2].draw_area(), ((120, 8), (128, 16)));
+    }
+
+    #[test]
+    fn distant_areas_keep_their_own_span() {
+        let mut dirty = DirtyPages::<8>::new(Size::new(128, 64));
+        let column = |x| Rectangle::new(Point::new(x, 0), Size::new(4, 8));
+        dirty.mark(&column(100));
+        dirty.mark(&column(10));
+        let spans: Vec<_, 4> = dirty
+            .spans()
+            .map(|s| (s.first_column, s.last_column))
+            .collect();
+        assert_eq!(spans, [(10, 13), (100, 103)]);
+
+        // Close enough to be merged with the first one
+        dirty.mark(&column(20));
+        dirty.mark(&column(50));
+        dirty.mark(&column(80));
+        let spans: Vec<_, 4> = dirty
+            .spans()
+            .map(|s| (s.first_column, s.last_column))
+            .collect();
+        assert_eq!(spans, [(10, 23), (50, 53), (80, 103)]);
+    }
+
+    #[test]
+    fn take_starts_over() {
+        let mut dirty = DirtyPages::<8>::new(Size::new(128, 64));
+        dirty.mark_all();
+        assert_eq!(dirty.take().byte_count(), 128 * 8);
+        assert!(dirty.is_empty());
+    }
+
+    #[test]
+    fn short_displays_only_use_their_pages() {
+        let mut dirty = DirtyPages::<8>::new(Size::new(128, 32));
+        dirty.mark(&Rectangle::new(Point::new(0, 28), Size::new(8, 8)));
+        let pages: Vec<_, 4> = dirty.spans().map(|s| s.page).collect();
+        assert_eq!(pages, [3]);
+
+        dirty.mark_all();
+        assert_eq!(dirty.byte_count(), 128 * 4);
+    }
+
+    #[test]
+    fn pixels_are_stored_in_pages() {
+        let mut buffer = PageBuffer::<128, 8>::new();
+        buffer
+            .draw_iter([
+                Pixel(Point::new(3, 0), BinaryColor::On),
+                Pixel(Point::new(3, 9), BinaryColor::On),
+                Pixel(Point::new(-1, 0), BinaryColor::On),
+                Pixel(Point::new(128, 10), BinaryColor::On),
+            ])
+            .unwrap();
+        assert_eq!(buffer.count_on(), 2);
+        assert!(buffer.pixel(Point::new(3, 9)));
+
+        let span = PageSpan {
+            page: 1,
+            first_column: 2,
+            last_column: 4,
+        };
+        assert_eq!(buffer.span_data(&span), [0x00, 0x02, 0x00]);
+    }
+}